@@ -0,0 +1,166 @@
+use std::fmt::{Display, Formatter};
+
+use crate::rolls::{ActionRoll, Dice, Momentum, Outcome, ProgressRoll};
+use crate::{InputType, OutputType};
+
+/// The exact chances of each outcome of an action or progress roll, found by trying every
+/// way the dice could land.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutcomeChances {
+    /// What was rolled, e.g. `Action Roll +2`.
+    pub title: String,
+    /// How many ways each outcome can happen, by `Outcome` from miss to strong hit.
+    pub outcomes: [OutputType; 3],
+    /// How many ways the challenge dice can match.
+    pub matches: OutputType,
+    /// How many ways the dice can land.
+    pub total: OutputType,
+}
+
+impl OutcomeChances {
+    /// The chances for an action roll with the given bonus.
+    /// With momentum, it is burnt whenever that would improve the outcome.
+    pub fn action(dice: Dice, bonus: InputType, momentum: Option<Momentum>) -> Self {
+        let title = match momentum {
+            Some(momentum) => format!("Action Roll +{} with momentum {}", bonus, momentum),
+            None => format!("Action Roll +{}", bonus),
+        };
+        let mut chances = Self::new(title);
+        for action_die in 1..=dice.action_die {
+            for challenge_dice in all_challenge_dice(dice) {
+                let roll = ActionRoll {
+                    dice,
+                    action_die,
+                    bonus: Some(bonus),
+                    stat: None,
+                    momentum,
+                    challenge_dice,
+                };
+                let outcome = roll.burn_outcome().or(roll.outcome()).unwrap();
+                chances.add(outcome, roll.is_match());
+            }
+        }
+        chances
+    }
+
+    /// The chances for a progress roll with the given progress score.
+    pub fn progress(dice: Dice, score: InputType) -> Self {
+        let mut chances = Self::new(format!("Progress Roll {}", score));
+        for challenge_dice in all_challenge_dice(dice) {
+            let roll = ProgressRoll {
+                dice,
+                bonus: Some(score),
+                challenge_dice,
+            };
+            chances.add(roll.outcome().unwrap(), roll.is_match());
+        }
+        chances
+    }
+
+    fn new(title: String) -> Self {
+        Self {
+            title,
+            outcomes: [0; 3],
+            matches: 0,
+            total: 0,
+        }
+    }
+
+    /// Count one more way the dice could land.
+    fn add(&mut self, outcome: Outcome, is_match: bool) {
+        self.outcomes[outcome as usize] += 1;
+        self.matches += OutputType::from(is_match);
+        self.total += 1;
+    }
+
+    /// How many ways the given outcome can happen.
+    pub fn ways(&self, outcome: Outcome) -> OutputType {
+        self.outcomes[outcome as usize]
+    }
+
+    /// Write how likely something is that happens in the given number of ways.
+    fn fmt_chance(&self, f: &mut Formatter<'_>, name: &str, ways: OutputType) -> std::fmt::Result {
+        let percent = f64::from(ways) * 100.0 / f64::from(self.total);
+        write!(
+            f,
+            "\n**{}**: {:.1}% ({}/{})",
+            name, percent, ways, self.total
+        )
+    }
+}
+
+/// Every way the challenge dice can land.
+fn all_challenge_dice(dice: Dice) -> impl Iterator<Item = [OutputType; 2]> {
+    let sides = dice.challenge_die;
+    (1..=sides).flat_map(move |first| (1..=sides).map(move |second| [first, second]))
+}
+
+impl Display for OutcomeChances {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "***Odds of {}***", self.title)?;
+        for outcome in [Outcome::StrongHit, Outcome::WeakHit, Outcome::Miss] {
+            self.fmt_chance(f, &outcome.to_string(), self.ways(outcome))?;
+        }
+        self.fmt_chance(f, "Match", self.matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ways(chances: &OutcomeChances) -> [OutputType; 3] {
+        [Outcome::StrongHit, Outcome::WeakHit, Outcome::Miss].map(|o| chances.ways(o))
+    }
+
+    #[test]
+    fn progress() {
+        let chances = OutcomeChances::progress(Dice::default(), 5);
+        // A strong hit needs both dice under 5, and a miss needs neither.
+        assert_eq!(ways(&chances), [16, 48, 36]);
+        assert_eq!(chances.matches, 10);
+        assert_eq!(chances.total, 100);
+        assert_eq!(
+            chances.to_string(),
+            "***Odds of Progress Roll 5***\n\
+**Strong Hit**: 16.0% (16/100)\n\
+**Weak Hit**: 48.0% (48/100)\n\
+**Miss**: 36.0% (36/100)\n\
+**Match**: 10.0% (10/100)"
+        );
+        // Scores are capped, so a pair of 10s always misses.
+        assert_eq!(
+            ways(&OutcomeChances::progress(Dice::default(), 12)),
+            [81, 18, 1]
+        );
+    }
+
+    #[test]
+    fn action() {
+        let chances = OutcomeChances::action(Dice::default(), 2, None);
+        assert_eq!(chances.total, 600);
+        assert_eq!(chances.matches, 60);
+        assert_eq!(ways(&chances).iter().sum::<OutputType>(), 600);
+        assert_eq!(ways(&chances), [139, 262, 199]);
+        assert_eq!(
+            ways(&OutcomeChances::action(Dice::default(), 9, None)),
+            [486, 108, 6]
+        );
+    }
+
+    #[test]
+    fn momentum() {
+        let without = OutcomeChances::action(Dice::default(), 2, None);
+        let with = OutcomeChances::action(Dice::default(), 2, Some(8));
+        assert_eq!(with.title, "Action Roll +2 with momentum 8");
+        // Burning momentum only ever helps.
+        assert!(with.ways(Outcome::StrongHit) > without.ways(Outcome::StrongHit));
+        assert!(with.ways(Outcome::Miss) < without.ways(Outcome::Miss));
+        // Momentum of 10 can't be beaten except by a pair of 10s.
+        let with = OutcomeChances::action(Dice::default(), 0, Some(10));
+        assert_eq!(ways(&with), [486, 108, 6]);
+        // Negative momentum can cancel the action die, and can't be burnt.
+        let with = OutcomeChances::action(Dice::default(), 2, Some(-2));
+        assert!(with.ways(Outcome::StrongHit) < without.ways(Outcome::StrongHit));
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rolls::{Momentum, MAX_MOMENTUM, MIN_MOMENTUM};
+
+/// One of a character's five stats, which are added to action rolls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Edge,
+    Heart,
+    Iron,
+    Shadow,
+    Wits,
+}
+
+impl Stat {
+    pub const ALL: [Self; 5] = [
+        Self::Edge,
+        Self::Heart,
+        Self::Iron,
+        Self::Shadow,
+        Self::Wits,
+    ];
+}
+
+impl FromStr for Stat {
+    type Err = ();
+
+    /// Parse a stat from its name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stat = match s.to_ascii_lowercase().as_str() {
+            "edge" => Self::Edge,
+            "heart" => Self::Heart,
+            "iron" => Self::Iron,
+            "shadow" => Self::Shadow,
+            "wits" => Self::Wits,
+            _ => return Err(()),
+        };
+        Ok(stat)
+    }
+}
+
+impl Display for Stat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Edge => "Edge",
+            Self::Heart => "Heart",
+            Self::Iron => "Iron",
+            Self::Shadow => "Shadow",
+            Self::Wits => "Wits",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One of a character's condition meters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Meter {
+    Health,
+    Spirit,
+    Supply,
+}
+
+impl Meter {
+    pub const ALL: [Self; 3] = [Self::Health, Self::Spirit, Self::Supply];
+}
+
+impl Display for Meter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Health => "Health",
+            Self::Spirit => "Spirit",
+            Self::Supply => "Supply",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Anything on a character sheet that has a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    Stat(Stat),
+    Meter(Meter),
+    Momentum,
+}
+
+impl Attribute {
+    /// The values this attribute may take.
+    pub fn range(&self) -> RangeInclusive<i8> {
+        match self {
+            Self::Stat(_) => 0..=5,
+            Self::Meter(_) => 0..=5,
+            Self::Momentum => MIN_MOMENTUM..=MAX_MOMENTUM,
+        }
+    }
+}
+
+impl FromStr for Attribute {
+    type Err = ();
+
+    /// Parse an attribute from its name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(stat) = s.parse() {
+            return Ok(Self::Stat(stat));
+        }
+        let attribute = match s.to_ascii_lowercase().as_str() {
+            "health" => Self::Meter(Meter::Health),
+            "spirit" => Self::Meter(Meter::Spirit),
+            "supply" => Self::Meter(Meter::Supply),
+            "momentum" => Self::Momentum,
+            _ => return Err(()),
+        };
+        Ok(attribute)
+    }
+}
+
+impl Display for Attribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stat(stat) => write!(f, "{}", stat),
+            Self::Meter(meter) => write!(f, "{}", meter),
+            Self::Momentum => write!(f, "Momentum"),
+        }
+    }
+}
+
+/// A character sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Character {
+    pub edge: i8,
+    pub heart: i8,
+    pub iron: i8,
+    pub shadow: i8,
+    pub wits: i8,
+    pub health: i8,
+    pub spirit: i8,
+    pub supply: i8,
+    pub momentum: Momentum,
+}
+
+impl Default for Character {
+    /// A new character with the lowest stats, full meters, and starting momentum.
+    fn default() -> Self {
+        Self {
+            edge: 1,
+            heart: 1,
+            iron: 1,
+            shadow: 1,
+            wits: 1,
+            health: 5,
+            spirit: 5,
+            supply: 5,
+            momentum: 2,
+        }
+    }
+}
+
+impl Character {
+    fn value_mut(&mut self, attribute: Attribute) -> &mut i8 {
+        match attribute {
+            Attribute::Stat(Stat::Edge) => &mut self.edge,
+            Attribute::Stat(Stat::Heart) => &mut self.heart,
+            Attribute::Stat(Stat::Iron) => &mut self.iron,
+            Attribute::Stat(Stat::Shadow) => &mut self.shadow,
+            Attribute::Stat(Stat::Wits) => &mut self.wits,
+            Attribute::Meter(Meter::Health) => &mut self.health,
+            Attribute::Meter(Meter::Spirit) => &mut self.spirit,
+            Attribute::Meter(Meter::Supply) => &mut self.supply,
+            Attribute::Momentum => &mut self.momentum,
+        }
+    }
+
+    /// The value of an attribute.
+    pub fn get(&self, attribute: Attribute) -> i8 {
+        match attribute {
+            Attribute::Stat(Stat::Edge) => self.edge,
+            Attribute::Stat(Stat::Heart) => self.heart,
+            Attribute::Stat(Stat::Iron) => self.iron,
+            Attribute::Stat(Stat::Shadow) => self.shadow,
+            Attribute::Stat(Stat::Wits) => self.wits,
+            Attribute::Meter(Meter::Health) => self.health,
+            Attribute::Meter(Meter::Spirit) => self.spirit,
+            Attribute::Meter(Meter::Supply) => self.supply,
+            Attribute::Momentum => self.momentum,
+        }
+    }
+
+    /// Set an attribute, if the value is in its range.
+    pub fn set(&mut self, attribute: Attribute, value: i8) -> bool {
+        if !attribute.range().contains(&value) {
+            return false;
+        }
+        *self.value_mut(attribute) = value;
+        true
+    }
+
+    /// Adjust an attribute by the given amount, stopping at the ends of its range.
+    pub fn adjust(&mut self, attribute: Attribute, amount: i8) {
+        let range = attribute.range();
+        let value = self.value_mut(attribute);
+        *value = value
+            .saturating_add(amount)
+            .clamp(*range.start(), *range.end());
+    }
+}
+
+impl Display for Character {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let list = |attributes: &mut dyn Iterator<Item = Attribute>| {
+            attributes
+                .map(|attribute| format!("{} {}", attribute, self.get(attribute)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        writeln!(
+            f,
+            "**Stats**: {}",
+            list(&mut Stat::ALL.into_iter().map(Attribute::Stat))
+        )?;
+        writeln!(
+            f,
+            "**Meters**: {}",
+            list(&mut Meter::ALL.into_iter().map(Attribute::Meter))
+        )?;
+        write!(f, "**Momentum**: {}", self.momentum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes() {
+        assert_eq!("WITS".parse(), Ok(Attribute::Stat(Stat::Wits)));
+        assert_eq!("supply".parse(), Ok(Attribute::Meter(Meter::Supply)));
+        assert_eq!("Momentum".parse(), Ok(Attribute::Momentum));
+        assert_eq!("luck".parse::<Attribute>(), Err(()));
+    }
+
+    #[test]
+    fn setting_and_adjusting() {
+        let mut character = Character::default();
+        assert!(character.set(Attribute::Stat(Stat::Iron), 3));
+        assert!(!character.set(Attribute::Stat(Stat::Iron), 6));
+        assert_eq!(character.iron, 3);
+        assert!(character.set(Attribute::Momentum, -6));
+        assert!(!character.set(Attribute::Meter(Meter::Health), -1));
+
+        // Adjustments stop at the ends of the range.
+        character.adjust(Attribute::Meter(Meter::Health), -3);
+        assert_eq!(character.health, 2);
+        character.adjust(Attribute::Meter(Meter::Health), -3);
+        assert_eq!(character.health, 0);
+        character.adjust(Attribute::Momentum, 20);
+        assert_eq!(character.momentum, 10);
+    }
+
+    #[test]
+    fn display() {
+        let character = Character {
+            edge: 3,
+            heart: 2,
+            ..Character::default()
+        };
+        assert_eq!(
+            character.to_string(),
+            "**Stats**: Edge 3, Heart 2, Iron 1, Shadow 1, Wits 1\n\
+**Meters**: Health 5, Spirit 5, Supply 5\n\
+**Momentum**: 2"
+        );
+    }
+}
@@ -0,0 +1,711 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::expr::{EvalError, Expr, Function};
+use crate::ratio::Ratio;
+use crate::rolls::{DiceGroup, KeepRule, RollSpec, MAX_EXTRA_DICE};
+use crate::{OutputType, SignedOutputType};
+
+/// The most steps of work that calculating a distribution may take.
+/// This keeps the calculation quick, however complicated the roll.
+const MAX_WORK: u64 = 20_000_000;
+
+/// Chances smaller than this are too small to show or to affect the statistics, so they stop
+/// being followed. This is what keeps rerolling and exploding dice finite.
+const NEGLIGIBLE: f64 = 1e-15;
+
+/// The most bars shown in a histogram; more results than this are grouped into ranges.
+const MAX_BARS: usize = 30;
+
+/// The length of the longest bar in a histogram.
+const BAR_LENGTH: f64 = 20.0;
+
+/// Why a distribution couldn't be calculated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistError {
+    /// Some results of the dice can't be evaluated, for example by dividing by zero.
+    Eval(EvalError),
+    /// The calculation would take more than `MAX_WORK` steps.
+    TooComplex,
+    /// Exploding dice add extra dice that could be kept or dropped, which isn't supported.
+    ExplodingKeep,
+    /// No total has any chance of being rolled.
+    NoResults,
+}
+
+impl From<EvalError> for DistError {
+    fn from(e: EvalError) -> Self {
+        Self::Eval(e)
+    }
+}
+
+impl Display for DistError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Eval(e) => write!(f, "{} for some results of the dice", e),
+            Self::TooComplex => write!(f, "Too complex to calculate exactly; try fewer dice"),
+            Self::ExplodingKeep => write!(
+                f,
+                "Can't calculate exactly when exploding dice are also kept or dropped"
+            ),
+            Self::NoResults => write!(f, "No results are possible"),
+        }
+    }
+}
+
+/// Counts the work done towards `MAX_WORK`.
+struct Budget(u64);
+
+impl Budget {
+    fn spend(&mut self, steps: usize) -> Result<(), DistError> {
+        self.0 = self
+            .0
+            .checked_sub(steps as u64)
+            .ok_or(DistError::TooComplex)?;
+        Ok(())
+    }
+}
+
+/// The chances of each whole number in a range, which is how the totals of dice are built up.
+#[derive(Debug, Clone, PartialEq)]
+struct Dense {
+    min: SignedOutputType,
+    probs: Vec<f64>,
+}
+
+impl Dense {
+    fn empty() -> Self {
+        Self {
+            min: 0,
+            probs: Vec::new(),
+        }
+    }
+
+    /// Certainly the given value.
+    fn point(value: SignedOutputType) -> Self {
+        Self {
+            min: value,
+            probs: vec![1.0],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.probs.is_empty()
+    }
+
+    /// Each value with a chance of happening, and that chance.
+    fn outcomes(&self) -> impl Iterator<Item = (SignedOutputType, f64)> + '_ {
+        (self.min..)
+            .zip(self.probs.iter().copied())
+            .filter(|&(_, prob)| prob > 0.0)
+    }
+
+    /// Add to the chance of the given value.
+    fn add(&mut self, value: SignedOutputType, prob: f64) {
+        if self.probs.is_empty() {
+            self.min = value;
+        }
+        if value < self.min {
+            let extra = (self.min - value) as usize;
+            self.probs.splice(0..0, std::iter::repeat_n(0.0, extra));
+            self.min = value;
+        }
+        let index = (value - self.min) as usize;
+        if index >= self.probs.len() {
+            self.probs.resize(index + 1, 0.0);
+        }
+        self.probs[index] += prob;
+    }
+
+    /// Add another distribution, shifted by `offset` and scaled by `prob`.
+    fn add_scaled(&mut self, other: &Self, offset: SignedOutputType, prob: f64) {
+        for (value, p) in other.outcomes() {
+            self.add(value + offset, p * prob);
+        }
+    }
+
+    /// The distribution of the sum of a result from each of the two distributions.
+    fn convolve(&self, other: &Self, budget: &mut Budget) -> Result<Self, DistError> {
+        budget.spend(self.probs.len() * other.probs.len())?;
+        let mut sum = Self::empty();
+        for (value, prob) in other.outcomes() {
+            sum.add_scaled(self, value, prob);
+        }
+        Ok(sum)
+    }
+
+    /// Forget values too unlikely to matter.
+    fn prune(&mut self) {
+        for prob in &mut self.probs {
+            if *prob < NEGLIGIBLE {
+                *prob = 0.0;
+            }
+        }
+        let Some(first) = self.probs.iter().position(|&prob| prob > 0.0) else {
+            *self = Self::empty();
+            return;
+        };
+        let last = self.probs.iter().rposition(|&prob| prob > 0.0).unwrap();
+        self.probs.truncate(last + 1);
+        self.probs.drain(..first);
+        self.min += first as SignedOutputType;
+    }
+
+    /// Scale the chances to add up to exactly one, after pruning.
+    fn normalise(mut self) -> Self {
+        let total = self.probs.iter().sum::<f64>();
+        for prob in &mut self.probs {
+            *prob /= total;
+        }
+        self
+    }
+}
+
+/// How much a die showing the given value adds to its group's total.
+fn contribution(group: &DiceGroup, roll: OutputType) -> SignedOutputType {
+    match group.successes {
+        Some(rule) => {
+            let success = rule.target.is_met(roll);
+            let failure = rule.failure.is_some_and(|failure| roll <= failure);
+            SignedOutputType::from(success) - SignedOutputType::from(failure)
+        }
+        None => roll.into(),
+    }
+}
+
+/// The distribution of what a single die adds to its group, following the same rerolls and
+/// explosions as `RolledGroup::random`.
+fn single_die(group: &DiceGroup, budget: &mut Budget) -> Result<Dense, DistError> {
+    let prob = 1.0 / f64::from(group.size);
+    let mut finished = Dense::empty();
+    // What has been added so far by dice still rolling, split by whether the current die has
+    // already been rerolled.
+    let mut rolling = [Dense::point(0), Dense::empty()];
+    for extra_dice in 0..=MAX_EXTRA_DICE {
+        let mut next = [Dense::empty(), Dense::empty()];
+        for (rerolled, so_far) in rolling.iter().enumerate() {
+            if so_far.is_empty() {
+                continue;
+            }
+            budget.spend(so_far.probs.len() * group.size as usize)?;
+            for roll in 1..=group.size {
+                let modified = extra_dice < MAX_EXTRA_DICE;
+                let is_rerolled = modified
+                    && group.reroll.is_some_and(|reroll| {
+                        roll <= reroll.threshold && !(reroll.once && rerolled == 1)
+                    });
+                let explodes = modified
+                    && !is_rerolled
+                    && group.explode.is_some_and(|explode| roll >= explode);
+                if is_rerolled {
+                    next[1].add_scaled(so_far, 0, prob);
+                } else if explodes {
+                    next[0].add_scaled(so_far, contribution(group, roll), prob);
+                } else {
+                    finished.add_scaled(so_far, contribution(group, roll), prob);
+                }
+            }
+        }
+        next.iter_mut().for_each(Dense::prune);
+        rolling = next;
+        if rolling.iter().all(Dense::is_empty) {
+            break;
+        }
+    }
+    Ok(finished)
+}
+
+/// The distribution of the total of a group of dice.
+fn group_total(group: &DiceGroup, budget: &mut Budget) -> Result<Dense, DistError> {
+    let Some(keep) = group.keep else {
+        let die = single_die(group, budget)?;
+        let mut total = Dense::point(0);
+        for _ in 0..group.count {
+            total = total.convolve(&die, budget)?;
+            total.prune();
+        }
+        return Ok(total.normalise());
+    };
+    if group.explode.is_some() {
+        return Err(DistError::ExplodingKeep);
+    }
+
+    // Without exploding, every die ends up showing a single value.
+    let faces = single_die(
+        &DiceGroup {
+            successes: None,
+            ..*group
+        },
+        budget,
+    )?
+    .normalise();
+    let count = group.count;
+    let (kept, highest) = match keep {
+        KeepRule::KeepHighest(n) => (n.min(count), true),
+        KeepRule::KeepLowest(n) => (n.min(count), false),
+        KeepRule::DropHighest(n) => (count.saturating_sub(n), false),
+        KeepRule::DropLowest(n) => (count.saturating_sub(n), true),
+    };
+    let mut faces = faces
+        .outcomes()
+        .map(|(roll, prob)| (roll as OutputType, prob))
+        .collect::<Vec<_>>();
+    if highest {
+        faces.reverse();
+    }
+    Ok(kept_total(group, &faces, count, kept, budget)?.normalise())
+}
+
+/// The distribution of the total of the first `kept` of `count` dice, where the chance of
+/// each face is given in the order the dice are kept.
+///
+/// Faces are considered one at a time. Given that the dice not yet accounted for show one of
+/// the remaining faces, how many of them show the current face is binomially distributed.
+fn kept_total(
+    group: &DiceGroup,
+    faces: &[(OutputType, f64)],
+    count: OutputType,
+    kept: OutputType,
+    budget: &mut Budget,
+) -> Result<Dense, DistError> {
+    let ln_factorials = (0..=count)
+        .scan(0.0, |sum, n| {
+            if n > 0 {
+                *sum += f64::from(n).ln();
+            }
+            Some(*sum)
+        })
+        .collect::<Vec<f64>>();
+    let binomial = |n: OutputType, k: OutputType, p: f64| -> f64 {
+        if p >= 1.0 {
+            return if k == n { 1.0 } else { 0.0 };
+        }
+        let ln =
+            ln_factorials[n as usize] - ln_factorials[k as usize] - ln_factorials[(n - k) as usize]
+                + f64::from(k) * p.ln()
+                + f64::from(n - k) * (1.0 - p).ln();
+        ln.exp()
+    };
+
+    let mut finished = Dense::empty();
+    // The totals so far, by how many dice have been kept, while more are still to be kept.
+    let mut keeping = vec![Dense::empty(); kept as usize];
+    match keeping.first_mut() {
+        Some(first) => *first = Dense::point(0),
+        None => return Ok(Dense::point(0)),
+    }
+    let mut remaining = 1.0;
+    for (i, &(roll, prob)) in faces.iter().enumerate() {
+        let value = contribution(group, roll);
+        let share = if i + 1 == faces.len() {
+            1.0
+        } else {
+            (prob / remaining).min(1.0)
+        };
+        remaining -= prob;
+        let mut next = vec![Dense::empty(); kept as usize];
+        for (so_far_kept, totals) in keeping.iter().enumerate() {
+            if totals.is_empty() {
+                continue;
+            }
+            let so_far_kept = so_far_kept as OutputType;
+            let left = count - so_far_kept;
+            let still_to_keep = kept - so_far_kept;
+            budget.spend(totals.probs.len() * still_to_keep as usize)?;
+            let mut accounted = 0.0;
+            for showing in 0..still_to_keep.min(left + 1) {
+                let chance = binomial(left, showing, share);
+                accounted += chance;
+                let offset = value * SignedOutputType::from(showing);
+                next[(so_far_kept + showing) as usize].add_scaled(totals, offset, chance);
+            }
+            // Any more showing this face fill up the dice to keep.
+            let rest = (1.0 - accounted).max(0.0);
+            let offset = value * SignedOutputType::from(still_to_keep);
+            finished.add_scaled(totals, offset, rest);
+        }
+        next.iter_mut().for_each(Dense::prune);
+        keeping = next;
+    }
+    Ok(finished)
+}
+
+/// The chances of each result of an expression.
+type Chances = BTreeMap<Ratio, f64>;
+
+/// Work out the chances of each result of an expression.
+fn chances(spec: &RollSpec, budget: &mut Budget) -> Result<Chances, DistError> {
+    let result = match spec {
+        Expr::Number(n) => Chances::from([(Ratio::integer((*n).into()), 1.0)]),
+        Expr::Dice(group) => group_total(group, budget)?
+            .outcomes()
+            .map(|(total, prob)| (Ratio::integer(total), prob))
+            .collect(),
+        Expr::Negate(inner) => map(&chances(inner, budget)?, |value| {
+            value.checked_neg().ok_or(EvalError::Overflow)
+        })?,
+        Expr::Binary(operator, lhs, rhs) => {
+            let lhs = chances(lhs, budget)?;
+            let rhs = chances(rhs, budget)?;
+            combine(&lhs, &rhs, budget, |lhs, rhs| operator.apply(lhs, rhs))?
+        }
+        Expr::Call(function @ (Function::Max | Function::Min), args) => {
+            let mut args = args.iter();
+            let mut result = chances(args.next().unwrap(), budget)?;
+            for arg in args {
+                let arg = chances(arg, budget)?;
+                result = combine(&result, &arg, budget, |lhs, rhs| {
+                    function.apply(vec![lhs, rhs])
+                })?;
+            }
+            result
+        }
+        Expr::Call(function, args) => map(&chances(&args[0], budget)?, |value| {
+            function.apply(vec![value])
+        })?,
+    };
+    Ok(result)
+}
+
+/// The chances of each result of applying a function to a result with the given chances.
+fn map(
+    chances: &Chances,
+    f: impl Fn(Ratio) -> Result<Ratio, EvalError>,
+) -> Result<Chances, DistError> {
+    let mut result = Chances::new();
+    for (&value, &prob) in chances {
+        *result.entry(f(value)?).or_default() += prob;
+    }
+    Ok(result)
+}
+
+/// The chances of each result of combining two independent results.
+fn combine(
+    lhs: &Chances,
+    rhs: &Chances,
+    budget: &mut Budget,
+    f: impl Fn(Ratio, Ratio) -> Result<Ratio, EvalError>,
+) -> Result<Chances, DistError> {
+    budget.spend(lhs.len() * rhs.len())?;
+    let mut result = Chances::new();
+    for (&left, &left_prob) in lhs {
+        for (&right, &right_prob) in rhs {
+            *result.entry(f(left, right)?).or_default() += left_prob * right_prob;
+        }
+    }
+    Ok(result)
+}
+
+/// Write a number to two decimal places, without any trailing zeros.
+fn decimal(x: f64) -> String {
+    let decimal = format!("{:.2}", x);
+    decimal
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// The exact probability distribution of a custom roll's total.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub spec: RollSpec,
+    /// Each possible total with its chance, from lowest to highest.
+    pub outcomes: Vec<(Ratio, f64)>,
+}
+
+impl Distribution {
+    /// Work out the distribution of a roll by combining the chances of every result of its
+    /// dice, rather than by rolling it many times.
+    pub fn of(spec: RollSpec) -> Result<Self, DistError> {
+        let mut budget = Budget(MAX_WORK);
+        let outcomes = chances(&spec, &mut budget)?
+            .into_iter()
+            .filter(|&(_, prob)| prob > 0.0)
+            .collect::<Vec<_>>();
+        if outcomes.is_empty() {
+            return Err(DistError::NoResults);
+        }
+        Ok(Self { spec, outcomes })
+    }
+
+    pub fn min(&self) -> Option<Ratio> {
+        self.outcomes.first().map(|&(value, _)| value)
+    }
+
+    pub fn max(&self) -> Option<Ratio> {
+        self.outcomes.last().map(|&(value, _)| value)
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.outcomes
+            .iter()
+            .map(|&(value, prob)| value.to_f64() * prob)
+            .sum()
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        let mean = self.mean();
+        let variance = self
+            .outcomes
+            .iter()
+            .map(|&(value, prob)| (value.to_f64() - mean).powi(2) * prob)
+            .sum::<f64>();
+        variance.sqrt()
+    }
+
+    /// The bars of a histogram: a label for the results in each, and their total chance.
+    /// When there are too many results, they are grouped into ranges of equal width.
+    fn bars(&self) -> Vec<(String, f64)> {
+        if self.outcomes.len() <= MAX_BARS {
+            return self
+                .outcomes
+                .iter()
+                .map(|&(value, prob)| (value.to_string(), prob))
+                .collect();
+        }
+        let (Some(min), Some(max)) = (self.min(), self.max()) else {
+            return Vec::new();
+        };
+        let min = min.to_f64();
+        let span = max.to_f64() - min;
+        let width = if self.outcomes.iter().all(|(value, _)| value.is_integer()) {
+            ((span + 1.0) / MAX_BARS as f64).ceil()
+        } else {
+            span / MAX_BARS as f64
+        };
+        let mut bars = Vec::<(Ratio, Ratio, f64)>::new();
+        let mut current_bar = None;
+        for &(value, prob) in &self.outcomes {
+            let bar = (((value.to_f64() - min) / width) as usize).min(MAX_BARS - 1);
+            match bars.last_mut() {
+                Some((_, last, total)) if current_bar == Some(bar) => {
+                    *last = value;
+                    *total += prob;
+                }
+                _ => bars.push((value, value, prob)),
+            }
+            current_bar = Some(bar);
+        }
+        bars.into_iter()
+            .map(|(first, last, prob)| {
+                let label = if first == last {
+                    first.to_string()
+                } else {
+                    format!("{}–{}", first, last)
+                };
+                (label, prob)
+            })
+            .collect()
+    }
+}
+
+impl Distribution {
+    /// The roll and its statistics, without the histogram.
+    pub fn summary(&self) -> String {
+        let heading = format!("***Distribution of {}***", self.spec);
+        let (Some(min), Some(max)) = (self.min(), self.max()) else {
+            return format!("{}\n{}", heading, DistError::NoResults);
+        };
+        format!(
+            "{}\n**Min**: {}, **Max**: {}, **Mean**: {}, **Standard Deviation**: {}",
+            heading,
+            min,
+            max,
+            decimal(self.mean()),
+            decimal(self.std_dev())
+        )
+    }
+}
+
+impl Display for Distribution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.summary())?;
+        let bars = self.bars();
+        if bars.is_empty() {
+            return Ok(());
+        }
+        let label_width = bars
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let highest = bars.iter().map(|&(_, prob)| prob).fold(0.0, f64::max);
+        write!(f, "\n```")?;
+        for (label, prob) in bars {
+            let length = (prob / highest * BAR_LENGTH).round() as usize;
+            write!(
+                f,
+                "\n{:>width$} {:>6.2}% {}",
+                label,
+                prob * 100.0,
+                "█".repeat(length),
+                width = label_width
+            )?;
+        }
+        write!(f, "\n```")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dist(spec: &str) -> Result<Distribution, DistError> {
+        Distribution::of(spec.parse().unwrap())
+    }
+
+    /// The chances of each total, which must all be whole numbers.
+    fn chances(spec: &str) -> Vec<(SignedOutputType, f64)> {
+        dist(spec)
+            .unwrap()
+            .outcomes
+            .iter()
+            .map(|&(value, prob)| {
+                assert!(value.is_integer());
+                (value.floor().to_f64() as SignedOutputType, prob)
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} should be {}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_chances(spec: &str, expected: &[(SignedOutputType, f64)]) {
+        let actual = chances(spec);
+        assert_eq!(actual.len(), expected.len(), "{}", spec);
+        for (&(value, prob), &(expected_value, expected_prob)) in actual.iter().zip(expected) {
+            assert_eq!(value, expected_value, "{}", spec);
+            assert_close(prob, expected_prob);
+        }
+    }
+
+    #[test]
+    fn sums() {
+        let d = dist("2d6 + 1").unwrap();
+        assert_eq!(d.min(), Some(Ratio::integer(3)));
+        assert_eq!(d.max(), Some(Ratio::integer(13)));
+        assert_close(d.mean(), 8.0);
+        assert_close(d.std_dev(), (35.0f64 / 6.0).sqrt());
+        assert_chances(
+            "2d6",
+            &(2..=12)
+                .map(|total: SignedOutputType| (total, (6 - (total - 7).abs()) as f64 / 36.0))
+                .collect::<Vec<_>>(),
+        );
+        let difference = (-3..=3)
+            .map(|n: SignedOutputType| (n, (4 - n.abs()) as f64 / 16.0))
+            .collect::<Vec<_>>();
+        assert_chances("d4 - d4", &difference);
+    }
+
+    #[test]
+    fn functions() {
+        assert_chances("max(d4, 2)", &[(2, 0.5), (3, 0.25), (4, 0.25)]);
+        assert_chances("floor(d4 / 2)", &[(0, 0.25), (1, 0.5), (2, 0.25)]);
+        let halves = dist("d2 / 2").unwrap();
+        assert_eq!(
+            halves.outcomes,
+            [(Ratio::new(1, 2).unwrap(), 0.5), (Ratio::integer(1), 0.5)]
+        );
+        assert_eq!(
+            dist("6 / (d2 - 1)"),
+            Err(DistError::Eval(EvalError::DivisionByZero))
+        );
+    }
+
+    #[test]
+    fn keeping() {
+        // The well-known distribution of ability scores.
+        let d = dist("4d6kh3").unwrap();
+        assert_eq!(d.min(), Some(Ratio::integer(3)));
+        assert_eq!(d.max(), Some(Ratio::integer(18)));
+        assert_close(d.mean(), 15869.0 / 1296.0);
+        let highest = (1..=20)
+            .map(|n| (n, (2 * n - 1) as f64 / 400.0))
+            .collect::<Vec<_>>();
+        assert_chances("2d20kh1", &highest);
+        let lowest = (1..=6)
+            .map(|n| (n, (13 - 2 * n) as f64 / 36.0))
+            .collect::<Vec<_>>();
+        assert_chances("2d6kl1", &lowest);
+        // Dropping is the same as keeping the others.
+        assert_eq!(chances("3d8dh1"), chances("3d8kl2"));
+        assert_eq!(chances("3d8dl2"), chances("3d8kh1"));
+        assert_eq!(dist("4d6!kh3"), Err(DistError::ExplodingKeep));
+    }
+
+    #[test]
+    fn rerolls_and_explosions() {
+        assert_chances("d6r2", &(3..=6).map(|n| (n, 0.25)).collect::<Vec<_>>());
+        assert_chances(
+            "d4ro1",
+            &[
+                (1, 1.0 / 16.0),
+                (2, 5.0 / 16.0),
+                (3, 5.0 / 16.0),
+                (4, 5.0 / 16.0),
+            ],
+        );
+        // An exploding die's expected value is scaled up by the chance of not exploding.
+        assert_close(dist("d6!").unwrap().mean(), 3.5 * 6.0 / 5.0);
+        assert_chances("d4ro1kh1", &chances("d4ro1"));
+    }
+
+    #[test]
+    fn pools() {
+        // Each die succeeds a third of the time.
+        let d = dist("6d6>=5").unwrap();
+        assert_close(d.mean(), 2.0);
+        assert_close(d.outcomes[0].1, (2.0f64 / 3.0).powi(6));
+        assert_chances("d6>=5f1", &[(-1, 1.0 / 6.0), (0, 0.5), (1, 1.0 / 3.0)]);
+        assert_chances("2d6kh1>=6", &[(0, 25.0 / 36.0), (1, 11.0 / 36.0)]);
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(dist("d10000!>2"), Err(DistError::TooComplex));
+        assert!(dist("100d20").is_ok());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            dist("d4 + 1").unwrap().to_string(),
+            "***Distribution of 1d4 + 1***\n\
+**Min**: 2, **Max**: 5, **Mean**: 3.5, **Standard Deviation**: 1.12\n\
+```\n\
+2  25.00% ████████████████████\n\
+3  25.00% ████████████████████\n\
+4  25.00% ████████████████████\n\
+5  25.00% ████████████████████\n\
+```"
+        );
+        assert_eq!(
+            dist("d4 + 1").unwrap().summary(),
+            "***Distribution of 1d4 + 1***\n\
+**Min**: 2, **Max**: 5, **Mean**: 3.5, **Standard Deviation**: 1.12"
+        );
+        // Many results are grouped into ranges.
+        let d = dist("10d10").unwrap();
+        let bars = d.bars();
+        assert!(bars.len() <= MAX_BARS);
+        assert_eq!(bars[0].0, "10–13");
+        assert_close(bars.iter().map(|&(_, prob)| prob).sum(), 1.0);
+        // A distribution without any results has no extremes to show.
+        let empty = Distribution {
+            outcomes: Vec::new(),
+            ..d
+        };
+        assert_eq!(empty.min(), None);
+        assert_eq!(empty.max(), None);
+        assert!(empty.bars().is_empty());
+        assert!(empty.to_string().ends_with("No results are possible"));
+        assert_eq!(empty.summary(), empty.to_string());
+    }
+}
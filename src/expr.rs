@@ -0,0 +1,280 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{ratio::Ratio, InputType};
+
+/// A binary arithmetic operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    /// How tightly this operator binds; higher binds tighter.
+    fn precedence(&self) -> Precedence {
+        match self {
+            Self::Add | Self::Subtract => Precedence::Sum,
+            Self::Multiply | Self::Divide => Precedence::Product,
+        }
+    }
+
+    /// Apply this operator to two values.
+    pub fn apply(&self, lhs: Ratio, rhs: Ratio) -> Result<Ratio, EvalError> {
+        match self {
+            Self::Add => lhs.checked_add(rhs).ok_or(EvalError::Overflow),
+            Self::Subtract => lhs.checked_sub(rhs).ok_or(EvalError::Overflow),
+            Self::Multiply => lhs.checked_mul(rhs).ok_or(EvalError::Overflow),
+            Self::Divide if rhs == Ratio::ZERO => Err(EvalError::DivisionByZero),
+            Self::Divide => lhs.checked_div(rhs).ok_or(EvalError::Overflow),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// A function that can be called within an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Max,
+    Min,
+    Floor,
+    Ceil,
+    Round,
+    Abs,
+}
+
+impl Function {
+    /// Look up a function by name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let function = match name.to_ascii_lowercase().as_str() {
+            "max" => Self::Max,
+            "min" => Self::Min,
+            "floor" => Self::Floor,
+            "ceil" => Self::Ceil,
+            "round" => Self::Round,
+            "abs" => Self::Abs,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    /// Can this function be called with this many arguments?
+    pub fn accepts(&self, num_args: usize) -> bool {
+        match self {
+            Self::Max | Self::Min => num_args >= 1,
+            Self::Floor | Self::Ceil | Self::Round | Self::Abs => num_args == 1,
+        }
+    }
+
+    /// Call this function with the given arguments, which it must accept.
+    pub fn apply(&self, args: Vec<Ratio>) -> Result<Ratio, EvalError> {
+        let result = match self {
+            Self::Max => args.into_iter().max().unwrap(),
+            Self::Min => args.into_iter().min().unwrap(),
+            Self::Floor => args[0].floor(),
+            Self::Ceil => args[0].ceil(),
+            Self::Round => args[0].round(),
+            Self::Abs => args[0].checked_abs().ok_or(EvalError::Overflow)?,
+        };
+        Ok(result)
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Max => "max",
+            Self::Min => "min",
+            Self::Floor => "floor",
+            Self::Ceil => "ceil",
+            Self::Round => "round",
+            Self::Abs => "abs",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How tightly part of an expression binds, for deciding where parentheses are needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Sum,
+    Product,
+    Negation,
+    Atom,
+}
+
+/// The dice at the leaves of an expression.
+pub trait Term: Display {
+    /// How this term binds when displayed.
+    /// For example, a term displayed as several dice reads like a sum.
+    fn precedence(&self) -> Precedence {
+        Precedence::Atom
+    }
+}
+
+/// Something that went wrong while evaluating an expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    DivisionByZero,
+    Overflow,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            Self::DivisionByZero => "Division by zero",
+            Self::Overflow => "Too large to calculate",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+/// An arithmetic expression with dice of type `D` at its leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr<D> {
+    Number(InputType),
+    Dice(D),
+    Negate(Box<Expr<D>>),
+    Binary(Operator, Box<Expr<D>>, Box<Expr<D>>),
+    Call(Function, Vec<Expr<D>>),
+}
+
+impl<D> Expr<D> {
+    /// Combine two expressions with a binary operator.
+    pub fn binary(operator: Operator, lhs: Self, rhs: Self) -> Self {
+        Self::Binary(operator, Box::new(lhs), Box::new(rhs))
+    }
+
+    /// All the dice in this expression, from left to right.
+    pub fn dice(&self) -> Vec<&D> {
+        let mut dice = Vec::new();
+        self.visit_dice(&mut |d| dice.push(d));
+        dice
+    }
+
+    fn visit_dice<'a>(&'a self, f: &mut impl FnMut(&'a D)) {
+        match self {
+            Self::Number(_) => {}
+            Self::Dice(d) => f(d),
+            Self::Negate(inner) => inner.visit_dice(f),
+            Self::Binary(_, lhs, rhs) => {
+                lhs.visit_dice(f);
+                rhs.visit_dice(f);
+            }
+            Self::Call(_, args) => {
+                for arg in args {
+                    arg.visit_dice(f);
+                }
+            }
+        }
+    }
+
+    /// Create an expression of the same shape, with every die transformed by `f`.
+    /// Dice are visited from left to right.
+    pub fn map_dice<E>(&self, f: &mut impl FnMut(&D) -> E) -> Expr<E> {
+        match self {
+            Self::Number(n) => Expr::Number(*n),
+            Self::Dice(d) => Expr::Dice(f(d)),
+            Self::Negate(inner) => Expr::Negate(Box::new(inner.map_dice(f))),
+            Self::Binary(operator, lhs, rhs) => {
+                let lhs = lhs.map_dice(f);
+                let rhs = rhs.map_dice(f);
+                Expr::binary(*operator, lhs, rhs)
+            }
+            Self::Call(function, args) => {
+                Expr::Call(*function, args.iter().map(|arg| arg.map_dice(f)).collect())
+            }
+        }
+    }
+
+    /// Evaluate this expression, using `value` to get the value of each die.
+    pub fn evaluate(&self, value: &impl Fn(&D) -> Ratio) -> Result<Ratio, EvalError> {
+        let result = match self {
+            Self::Number(n) => Ratio::integer((*n).into()),
+            Self::Dice(d) => value(d),
+            Self::Negate(inner) => inner
+                .evaluate(value)?
+                .checked_neg()
+                .ok_or(EvalError::Overflow)?,
+            Self::Binary(operator, lhs, rhs) => {
+                operator.apply(lhs.evaluate(value)?, rhs.evaluate(value)?)?
+            }
+            Self::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                function.apply(args)?
+            }
+        };
+        Ok(result)
+    }
+}
+
+impl<D: Term> Expr<D> {
+    /// How tightly this expression binds when displayed.
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            Self::Number(_) | Self::Call(..) => Precedence::Atom,
+            Self::Dice(d) => d.precedence(),
+            Self::Negate(_) => Precedence::Negation,
+            Self::Binary(operator, ..) => operator.precedence(),
+        }
+    }
+
+    /// Display this expression, wrapped in parentheses if it binds more loosely than `min`.
+    fn fmt_within(&self, f: &mut Formatter<'_>, min: Precedence) -> std::fmt::Result {
+        if self.precedence() < min {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl<D: Term> Display for Expr<D> {
+    /// Display this expression with only the parentheses needed to preserve its structure.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{}", n),
+            Self::Dice(d) => write!(f, "{}", d),
+            Self::Negate(inner) => {
+                write!(f, "-")?;
+                inner.fmt_within(f, Precedence::Atom)
+            }
+            Self::Binary(operator, lhs, rhs) => {
+                // Operators are left-associative, so an equally-binding
+                // right-hand side needs parentheses.
+                let precedence = operator.precedence();
+                lhs.fmt_within(f, precedence)?;
+                write!(f, " {} ", operator)?;
+                match precedence {
+                    Precedence::Sum => rhs.fmt_within(f, Precedence::Product),
+                    _ => rhs.fmt_within(f, Precedence::Negation),
+                }
+            }
+            Self::Call(function, args) => {
+                write!(f, "{}(", function)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
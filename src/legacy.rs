@@ -0,0 +1,271 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rolls::Outcome;
+use crate::tracks::{render_boxes, Rank, TrackKind, BOXES, TICKS_PER_BOX};
+use crate::InputType;
+
+/// The number of ticks that fill a legacy track once.
+const TICKS_PER_LAP: u16 = (BOXES * TICKS_PER_BOX) as u16;
+
+/// One of the three legacy tracks that every character has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyKind {
+    Quests,
+    Bonds,
+    Discoveries,
+}
+
+impl LegacyKind {
+    /// The legacy track rewarded for completing a progress track of the given kind, if any.
+    pub fn rewarded_by(kind: TrackKind) -> Option<Self> {
+        match kind {
+            TrackKind::Vow => Some(Self::Quests),
+            TrackKind::Connection => Some(Self::Bonds),
+            TrackKind::Expedition => Some(Self::Discoveries),
+            TrackKind::Combat => None,
+        }
+    }
+}
+
+impl FromStr for LegacyKind {
+    type Err = ();
+
+    /// Parse a legacy track from its name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s.to_ascii_lowercase().as_str() {
+            "quests" | "quest" => Self::Quests,
+            "bonds" | "bond" => Self::Bonds,
+            "discoveries" | "discovery" => Self::Discoveries,
+            _ => return Err(()),
+        };
+        Ok(kind)
+    }
+}
+
+impl Display for LegacyKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Quests => "Quests",
+            Self::Bonds => "Bonds",
+            Self::Discoveries => "Discoveries",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The number of legacy ticks rewarded for completing a progress track.
+/// A strong hit rewards according to the track's rank; a weak hit as if it were one rank lower.
+pub fn reward(rank: Rank, outcome: Outcome) -> u16 {
+    let rank = match outcome {
+        Outcome::StrongHit => rank,
+        Outcome::WeakHit => match rank {
+            Rank::Troublesome => return 0,
+            Rank::Dangerous => Rank::Troublesome,
+            Rank::Formidable => Rank::Dangerous,
+            Rank::Extreme => Rank::Formidable,
+            Rank::Epic => Rank::Extreme,
+        },
+        Outcome::Miss => return 0,
+    };
+    let per_box = u16::from(TICKS_PER_BOX);
+    match rank {
+        Rank::Troublesome => 1,
+        Rank::Dangerous => 2,
+        Rank::Formidable => per_box,
+        Rank::Extreme => 2 * per_box,
+        Rank::Epic => 3 * per_box,
+    }
+}
+
+/// A legacy track, which can be filled more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegacyTrack {
+    /// The total number of ticks ever marked, including on earlier laps.
+    pub ticks: u16,
+}
+
+impl LegacyTrack {
+    /// Mark the given number of ticks.
+    pub fn mark(&mut self, ticks: u16) {
+        self.ticks = self.ticks.saturating_add(ticks);
+    }
+
+    /// The total number of boxes ever filled.
+    pub fn boxes(&self) -> u16 {
+        self.ticks / u16::from(TICKS_PER_BOX)
+    }
+
+    /// Has this track been filled at least once?
+    pub fn is_filled(&self) -> bool {
+        self.ticks >= TICKS_PER_LAP
+    }
+
+    /// The score for a continuity roll: the number of filled boxes, or 10 if ever filled.
+    pub fn score(&self) -> InputType {
+        self.boxes().min(BOXES.into())
+    }
+
+    /// The experience earned from this track.
+    /// Each box is worth 2 XP the first time around, and 1 XP after that.
+    pub fn xp(&self) -> u16 {
+        let first_lap = self.score();
+        2 * first_lap + (self.boxes() - first_lap)
+    }
+}
+
+impl Display for LegacyTrack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // After the first lap, the track is cleared and marked again.
+        let current = if self.is_filled() {
+            (self.ticks - TICKS_PER_LAP) % TICKS_PER_LAP
+        } else {
+            self.ticks
+        };
+        // This always fits, as it's less than a full lap.
+        let current = current as u8;
+        write!(
+            f,
+            "{} {}/{}",
+            render_boxes(current),
+            current / TICKS_PER_BOX,
+            BOXES
+        )?;
+        if self.is_filled() {
+            write!(f, " (filled ✓)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A character's legacy tracks and experience.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Legacy {
+    pub quests: LegacyTrack,
+    pub bonds: LegacyTrack,
+    pub discoveries: LegacyTrack,
+    pub xp_spent: u16,
+}
+
+impl Legacy {
+    /// The legacy track of the given kind.
+    pub fn track(&self, kind: LegacyKind) -> &LegacyTrack {
+        match kind {
+            LegacyKind::Quests => &self.quests,
+            LegacyKind::Bonds => &self.bonds,
+            LegacyKind::Discoveries => &self.discoveries,
+        }
+    }
+
+    /// The legacy track of the given kind, to be changed.
+    pub fn track_mut(&mut self, kind: LegacyKind) -> &mut LegacyTrack {
+        match kind {
+            LegacyKind::Quests => &mut self.quests,
+            LegacyKind::Bonds => &mut self.bonds,
+            LegacyKind::Discoveries => &mut self.discoveries,
+        }
+    }
+
+    /// The total experience earned from all legacy tracks.
+    pub fn xp_earned(&self) -> u16 {
+        self.quests.xp() + self.bonds.xp() + self.discoveries.xp()
+    }
+
+    /// The experience available to spend.
+    pub fn xp_available(&self) -> u16 {
+        self.xp_earned().saturating_sub(self.xp_spent)
+    }
+
+    /// Spend some experience, if enough is available.
+    pub fn spend(&mut self, xp: u16) -> bool {
+        if xp > self.xp_available() {
+            return false;
+        }
+        self.xp_spent += xp;
+        true
+    }
+}
+
+impl Display for Legacy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for kind in [
+            LegacyKind::Quests,
+            LegacyKind::Bonds,
+            LegacyKind::Discoveries,
+        ] {
+            writeln!(f, "**{}**: {}", kind, self.track(kind))?;
+        }
+        write!(
+            f,
+            "**XP**: {} earned, {} spent, {} available",
+            self.xp_earned(),
+            self.xp_spent,
+            self.xp_available()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewards() {
+        let cases = [
+            // (rank, strong hit ticks, weak hit ticks)
+            (Rank::Troublesome, 1, 0),
+            (Rank::Dangerous, 2, 1),
+            (Rank::Formidable, 4, 2),
+            (Rank::Extreme, 8, 4),
+            (Rank::Epic, 12, 8),
+        ];
+        for (rank, strong, weak) in cases {
+            assert_eq!(reward(rank, Outcome::StrongHit), strong, "{}", rank);
+            assert_eq!(reward(rank, Outcome::WeakHit), weak, "{}", rank);
+            assert_eq!(reward(rank, Outcome::Miss), 0, "{}", rank);
+        }
+    }
+
+    #[test]
+    fn experience() {
+        let track = |ticks| LegacyTrack { ticks };
+        assert_eq!(track(3).xp(), 0);
+        assert_eq!(track(4).xp(), 2);
+        assert_eq!(track(40).xp(), 20);
+        // Boxes after the first lap are worth less.
+        assert_eq!(track(48).xp(), 22);
+        assert_eq!(track(48).score(), 10);
+        assert_eq!(track(13).score(), 3);
+
+        let mut legacy = Legacy {
+            quests: track(8),
+            bonds: track(4),
+            ..Legacy::default()
+        };
+        assert_eq!(legacy.xp_available(), 6);
+        assert!(legacy.spend(5));
+        assert!(!legacy.spend(2));
+        assert_eq!(legacy.xp_available(), 1);
+    }
+
+    #[test]
+    fn overflow_display() {
+        let track = |ticks| LegacyTrack { ticks }.to_string();
+        assert_eq!(track(6), "●◑○○○○○○○○ 1/10");
+        assert_eq!(track(40), "○○○○○○○○○○ 0/10 (filled ✓)");
+        assert_eq!(track(45), "●◔○○○○○○○○ 1/10 (filled ✓)");
+    }
+
+    #[test]
+    fn rewarded_tracks() {
+        assert_eq!(
+            LegacyKind::rewarded_by(TrackKind::Vow),
+            Some(LegacyKind::Quests)
+        );
+        assert_eq!(LegacyKind::rewarded_by(TrackKind::Combat), None);
+        assert_eq!("Discoveries".parse(), Ok(LegacyKind::Discoveries));
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt::{Display, Formatter};
+
+use serenity::prelude::TypeMapKey;
+
+use crate::{rolls::RollSpec, InputType, OutputType};
+
+/// Limits on how large a roll may be, configurable per deployment.
+/// These keep responses to a sensible size and rolling cheap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The most dice that may be rolled in a single custom roll.
+    pub max_dice: OutputType,
+    /// The most sides that a die in a custom roll may have.
+    pub max_sides: OutputType,
+    /// The most oracles that may be rolled at once.
+    pub max_oracle_rolls: InputType,
+    /// The largest total bonus that an action roll may have.
+    pub max_bonus: InputType,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_dice: 1000,
+            max_sides: 10000,
+            max_oracle_rolls: 500,
+            max_bonus: 100,
+        }
+    }
+}
+
+/// A request that goes over one of the configured limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitError {
+    TooManyDice(OutputType),
+    TooManySides(OutputType),
+    TooManyOracleRolls(InputType),
+    BonusTooLarge(InputType),
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyDice(limit) => {
+                write!(
+                    f,
+                    "Over the limit: at most {} dice can be rolled at once",
+                    limit
+                )
+            }
+            Self::TooManySides(limit) => {
+                write!(f, "Over the limit: dice can have at most {} sides", limit)
+            }
+            Self::TooManyOracleRolls(limit) => write!(
+                f,
+                "Over the limit: at most {} oracles can be rolled at once",
+                limit
+            ),
+            Self::BonusTooLarge(limit) => {
+                write!(
+                    f,
+                    "Over the limit: the total bonus can be at most {}",
+                    limit
+                )
+            }
+        }
+    }
+}
+
+impl Limits {
+    /// Check that a custom roll is within the limits.
+    /// The number of dice is counted across every group in the roll.
+    pub fn check_spec(&self, spec: &RollSpec) -> Result<(), LimitError> {
+        let groups = spec.dice();
+        let total_dice = groups
+            .iter()
+            .map(|group| u64::from(group.count))
+            .sum::<u64>();
+        if total_dice > self.max_dice.into() {
+            return Err(LimitError::TooManyDice(self.max_dice));
+        }
+        if groups.iter().any(|group| group.size > self.max_sides) {
+            return Err(LimitError::TooManySides(self.max_sides));
+        }
+        Ok(())
+    }
+
+    /// Check that a single die, such as the one for an oracle table, is within the limits.
+    pub fn check_sides(&self, sides: OutputType) -> Result<(), LimitError> {
+        if sides > self.max_sides {
+            return Err(LimitError::TooManySides(self.max_sides));
+        }
+        Ok(())
+    }
+
+    /// Check that a number of oracle rolls is within the limits.
+    pub fn check_oracle_rolls(&self, num_rolls: InputType) -> Result<(), LimitError> {
+        if num_rolls > self.max_oracle_rolls {
+            return Err(LimitError::TooManyOracleRolls(self.max_oracle_rolls));
+        }
+        Ok(())
+    }
+
+    /// Add up a list of bonuses, checking that the total is within the limits.
+    pub fn total_bonus(
+        &self,
+        bonuses: impl IntoIterator<Item = InputType>,
+    ) -> Result<InputType, LimitError> {
+        let mut total: InputType = 0;
+        for bonus in bonuses {
+            total = total
+                .checked_add(bonus)
+                .filter(|&total| total <= self.max_bonus)
+                .ok_or(LimitError::BonusTooLarge(self.max_bonus))?;
+        }
+        Ok(total)
+    }
+}
+
+impl TypeMapKey for Limits {
+    type Value = Limits;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> Limits {
+        Limits {
+            max_dice: 10,
+            max_sides: 100,
+            max_oracle_rolls: 5,
+            max_bonus: 20,
+        }
+    }
+
+    #[test]
+    fn dice_limits() {
+        let check = |spec: &str| limits().check_spec(&spec.parse().unwrap());
+        check("10d100").unwrap();
+        check("4d6 + 6d8 + 5").unwrap();
+        assert_eq!(check("11d6"), Err(LimitError::TooManyDice(10)));
+        assert_eq!(check("6d6 - 5d6"), Err(LimitError::TooManyDice(10)));
+        assert_eq!(
+            check("4294967295d6 + 4294967295d6"),
+            Err(LimitError::TooManyDice(10))
+        );
+        assert_eq!(check("d101"), Err(LimitError::TooManySides(100)));
+        limits().check_sides(100).unwrap();
+        assert_eq!(
+            limits().check_sides(101),
+            Err(LimitError::TooManySides(100))
+        );
+    }
+
+    #[test]
+    fn oracle_limits() {
+        limits().check_oracle_rolls(5).unwrap();
+        assert_eq!(
+            limits().check_oracle_rolls(6),
+            Err(LimitError::TooManyOracleRolls(5))
+        );
+    }
+
+    #[test]
+    fn bonus_limits() {
+        assert_eq!(limits().total_bonus([]), Ok(0));
+        assert_eq!(limits().total_bonus([3, 2, 15]), Ok(20));
+        assert_eq!(
+            limits().total_bonus([3, 2, 16]),
+            Err(LimitError::BonusTooLarge(20))
+        );
+        // Overflow is caught rather than wrapping or panicking.
+        let unlimited = Limits {
+            max_bonus: InputType::MAX,
+            ..limits()
+        };
+        assert_eq!(
+            unlimited.total_bonus([InputType::MAX, 1]),
+            Err(LimitError::BonusTooLarge(InputType::MAX))
+        );
+    }
+}
@@ -1,4 +1,9 @@
 use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use serenity::async_trait;
 use serenity::client::{Client, Context, EventHandler};
@@ -6,22 +11,84 @@ use serenity::framework::standard::{
     macros::{command, group},
     CommandResult, Configuration, StandardFramework,
 };
-use serenity::model::{channel::Message, gateway::GatewayIntents};
+use serenity::model::application::interaction::Interaction;
+use serenity::model::gateway::{GatewayIntents, Ready};
+use serenity::model::{
+    channel::Message,
+    id::{ChannelId, GuildId, UserId},
+};
 
-use crate::rolls::{ActionRoll, CustomRoll, OracleRoll, ProgressRoll};
+use crate::chances::OutcomeChances;
+use crate::character::{Attribute, Stat};
+use crate::distribution::Distribution;
+use crate::legacy::LegacyKind;
+use crate::limits::Limits;
+use crate::moves::{Move, MoveRoll};
+use crate::oracles::{CustomOracles, OracleTable, Oracles, TableRolls};
+use crate::output::Response;
+use crate::player::{Player, Players};
+use crate::rng::RngProvider;
+use crate::rolls::{
+    ActionRoll, AskRoll, CustomRoll, Dice, Momentum, Odds, OracleRoll, Outcome, ProgressRoll,
+    RollSpec, MAX_MOMENTUM, MIN_MOMENTUM,
+};
+use crate::ruleset::{Ruleset, RulesetId, Rulesets};
+use crate::settings::{GuildSettings, Settings};
+use crate::store::Store;
+use crate::table_import::Format;
+use crate::tracks::{ProgressTrack, Rank, TrackKind};
 
+mod chances;
+mod character;
+mod distribution;
+mod expr;
+mod legacy;
+mod limits;
+mod moves;
+mod oracles;
+mod output;
 mod parse_roll_spec;
+mod player;
+mod ratio;
+mod rng;
 mod rolls;
+mod ruleset;
+mod settings;
+mod slash;
+mod store;
+mod table_import;
+mod tracks;
 
 /// The numeric type used when parsing inputs.
-type InputType = u8;
+type InputType = u16;
 /// The numeric type used for intermediate computations and outputs.
 type OutputType = u32;
+/// The signed numeric type used for computations and outputs that may be negative.
+type SignedOutputType = i64;
 
 const DEFAULT_COMMAND_PREFIX: &str = "!";
 const COMMAND_PREFIX_ENVVAR: &str = "STARFORGED_COMMAND_PREFIX";
 const TOKEN_ENVVAR: &str = "STARFORGED_DISCORD_TOKEN";
 const MISSING_TOKEN_ERROR: &str = "Missing STARFORGED_DISCORD_TOKEN environment variable";
+const RNG_SEED_ENVVAR: &str = "STARFORGED_RNG_SEED";
+const INVALID_SEED_ERROR: &str = "STARFORGED_RNG_SEED must be an unsigned 64-bit integer";
+const MAX_DICE_ENVVAR: &str = "STARFORGED_MAX_DICE";
+const MAX_SIDES_ENVVAR: &str = "STARFORGED_MAX_SIDES";
+const MAX_ORACLE_ROLLS_ENVVAR: &str = "STARFORGED_MAX_ORACLE_ROLLS";
+const MAX_BONUS_ENVVAR: &str = "STARFORGED_MAX_BONUS";
+const DATA_DIR_ENVVAR: &str = "STARFORGED_DATA_DIR";
+const MESSAGE_CONTENT_ENVVAR: &str = "STARFORGED_MESSAGE_CONTENT";
+const INVALID_MESSAGE_CONTENT_ERROR: &str = "STARFORGED_MESSAGE_CONTENT must be true or false";
+const PLAYERS_FILE: &str = "players.json";
+const CUSTOM_ORACLES_FILE: &str = "custom_oracles.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+/// The largest file that can be imported as an oracle table, in bytes.
+const MAX_IMPORT_SIZE: u64 = 100_000;
+/// The argument that replaces a challenge die with the cursed die.
+const CURSED: &str = "cursed";
+/// The reply to someone changing what the bot does for a guild without permission.
+const MANAGE_GUILD_ERROR: &str = "Only members with the Manage Server permission can change this";
 
 /// The group of all our commands.
 #[group]
@@ -29,18 +96,51 @@ const MISSING_TOKEN_ERROR: &str = "Missing STARFORGED_DISCORD_TOKEN environment
     ping,
     help,
     action_roll,
+    move_roll,
+    move_reference,
     progress_roll,
     oracle_roll,
+    ask_oracle,
+    odds,
     custom_roll,
+    dist,
+    track,
+    legacy,
+    character,
+    settings,
     download
 )]
 struct Commands;
 
 /// Our request handler.
-struct Handler;
+#[derive(Default)]
+struct Handler {
+    /// Whether the slash commands have been registered, since `ready` fires again on
+    /// every reconnection.
+    registered: AtomicBool,
+}
 
 #[async_trait]
-impl EventHandler for Handler {}
+impl EventHandler for Handler {
+    async fn ready(&self, ctx: Context, _ready: Ready) {
+        if self.registered.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Err(e) = slash::register(&ctx).await {
+            eprintln!("Error registering slash commands: {:?}", e);
+            // Try again next time.
+            self.registered.store(false, Ordering::SeqCst);
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::ApplicationCommand(command) = interaction {
+            if let Err(e) = slash::handle(&ctx, &command).await {
+                eprintln!("Error handling /{}: {:?}", command.data.name, e);
+            }
+        }
+    }
+}
 
 fn framework_config(config: &mut Configuration) -> &mut Configuration {
     let prefix =
@@ -48,6 +148,69 @@ fn framework_config(config: &mut Configuration) -> &mut Configuration {
     config.prefix(prefix)
 }
 
+/// Create the bot's random number generator, seeding it from the environment if requested.
+fn rng_provider() -> RngProvider {
+    match env::var(RNG_SEED_ENVVAR) {
+        Ok(seed) => RngProvider::seeded(seed.trim().parse().expect(INVALID_SEED_ERROR)),
+        Err(_) => RngProvider::from_entropy(),
+    }
+}
+
+/// Read a single limit from the environment, falling back to the default if unset.
+fn env_limit<T: FromStr>(envvar: &str, default: T) -> T {
+    match env::var(envvar) {
+        Ok(limit) => limit
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be an unsigned integer", envvar)),
+        Err(_) => default,
+    }
+}
+
+/// Read the bot's limits, overriding the defaults from the environment if requested.
+fn configured_limits() -> Limits {
+    let default = Limits::default();
+    Limits {
+        max_dice: env_limit(MAX_DICE_ENVVAR, default.max_dice),
+        max_sides: env_limit(MAX_SIDES_ENVVAR, default.max_sides),
+        max_oracle_rolls: env_limit(MAX_ORACLE_ROLLS_ENVVAR, default.max_oracle_rolls),
+        max_bonus: env_limit(MAX_BONUS_ENVVAR, default.max_bonus),
+    }
+}
+
+/// The gateway intents to connect with.
+/// Prefix commands need the privileged message content intent, but slash commands work without
+/// it, so it can be turned off for bots that haven't been granted it.
+fn gateway_intents() -> GatewayIntents {
+    let message_content = match env::var(MESSAGE_CONTENT_ENVVAR) {
+        Ok(enabled) => enabled.trim().parse().expect(INVALID_MESSAGE_CONTENT_ERROR),
+        Err(_) => true,
+    };
+    if message_content {
+        GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT
+    } else {
+        println!(
+            "{} is false; only slash commands will work in servers",
+            MESSAGE_CONTENT_ENVVAR
+        );
+        GatewayIntents::GUILD_MESSAGES
+    }
+}
+
+/// Where to save the bot's data, if anywhere.
+fn data_dir() -> Option<PathBuf> {
+    env::var(DATA_DIR_ENVVAR).ok().map(PathBuf::from)
+}
+
+/// Open a store of records, saved in the data directory if there is one.
+fn open_store<T>(data_dir: &Option<PathBuf>, filename: &str) -> Store<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + Clone + Default + Send + 'static,
+{
+    let path = data_dir.as_ref().map(|dir| dir.join(filename));
+    Store::open(path).unwrap_or_else(|e| panic!("Error loading {}: {}", filename, e))
+}
+
 #[tokio::main]
 async fn main() {
     // Create our framework, specifying the command prefix and commands.
@@ -57,14 +220,36 @@ async fn main() {
 
     // Create our client and log in.
     let token = env::var(TOKEN_ENVVAR).expect(MISSING_TOKEN_ERROR);
-    let mut client = Client::builder(
-        token,
-        GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT,
-    )
-    .event_handler(Handler)
-    .framework(framework)
-    .await
-    .expect("Error creating client");
+    let mut client = Client::builder(token, gateway_intents())
+        .event_handler(Handler::default())
+        .framework(framework)
+        .await
+        .expect("Error creating client");
+
+    // Set up our shared random number generator.
+    // The seed is always reported so that a session can be replayed later.
+    let rng = rng_provider();
+    println!("Using RNG seed {}", rng.seed());
+    {
+        let mut data = client.data.write().await;
+        data.insert::<RngProvider>(Arc::new(rng));
+        // Set up the limits on what users may roll.
+        data.insert::<Limits>(configured_limits());
+        data.insert::<Rulesets>(Arc::new(Rulesets::bundled()));
+
+        // Load our saved data.
+        let data_dir = data_dir();
+        match &data_dir {
+            Some(dir) => {
+                fs::create_dir_all(dir).expect("Error creating data directory");
+                println!("Saving data in {}", dir.display());
+            }
+            None => println!("{} not set; data will not be saved", DATA_DIR_ENVVAR),
+        }
+        data.insert::<Players>(Arc::new(open_store(&data_dir, PLAYERS_FILE)));
+        data.insert::<CustomOracles>(Arc::new(open_store(&data_dir, CUSTOM_ORACLES_FILE)));
+        data.insert::<GuildSettings>(Arc::new(open_store(&data_dir, SETTINGS_FILE)));
+    }
 
     // Enter main command loop.
     if let Err(e) = client.start().await {
@@ -79,6 +264,144 @@ macro_rules! send {
     };
 }
 
+/// Send a response, which may be split across several messages or attached as a file.
+async fn send_response(ctx: &Context, msg: &Message, response: Response) -> CommandResult {
+    match response {
+        Response::Messages(messages) => {
+            for message in messages {
+                send!(ctx, msg, message).await?;
+            }
+        }
+        Response::Attachment {
+            message,
+            filename,
+            contents,
+        } => {
+            msg.channel_id
+                .send_message(ctx, |m| {
+                    m.content(message)
+                        .add_file((contents.as_bytes(), filename.as_str()))
+                })
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Get the bot's shared random number generator.
+async fn rng(ctx: &Context) -> Arc<RngProvider> {
+    let data = ctx.data.read().await;
+    data.get::<RngProvider>()
+        .cloned()
+        .expect("RngProvider missing from client data")
+}
+
+/// Get the bot's configured limits.
+async fn limits(ctx: &Context) -> Limits {
+    let data = ctx.data.read().await;
+    *data
+        .get::<Limits>()
+        .expect("Limits missing from client data")
+}
+
+/// Where a command was given, and by whom.
+/// Commands arrive either as messages or as slash commands.
+#[derive(Debug, Clone, Copy)]
+struct Origin {
+    guild: Option<GuildId>,
+    channel: ChannelId,
+    user: UserId,
+}
+
+impl From<&Message> for Origin {
+    fn from(msg: &Message) -> Self {
+        Self {
+            guild: msg.guild_id,
+            channel: msg.channel_id,
+            user: msg.author.id,
+        }
+    }
+}
+
+/// Get the ruleset in use where a command was given.
+async fn ruleset(ctx: &Context, origin: impl Into<Origin>) -> Arc<dyn Ruleset> {
+    let origin = origin.into();
+    let id = guild_settings(ctx)
+        .await
+        .get(&guild_key(origin))
+        .await
+        .ruleset_in(origin.channel.0);
+    let data = ctx.data.read().await;
+    data.get::<Rulesets>()
+        .expect("Rulesets missing from client data")
+        .get(id)
+}
+
+/// Get the oracle tables available where a command was given, including custom ones.
+async fn oracles(ctx: &Context, origin: impl Into<Origin>) -> Oracles {
+    let origin = origin.into();
+    let custom = custom_oracles(ctx).await.get(&guild_key(origin)).await;
+    ruleset(ctx, origin).await.oracles().with_custom(custom)
+}
+
+/// Get the store of every guild's custom oracle tables.
+async fn custom_oracles(ctx: &Context) -> Arc<Store<Vec<OracleTable>>> {
+    let data = ctx.data.read().await;
+    data.get::<CustomOracles>()
+        .cloned()
+        .expect("CustomOracles missing from client data")
+}
+
+/// Get the store of all players.
+async fn players(ctx: &Context) -> Arc<Store<Player>> {
+    let data = ctx.data.read().await;
+    data.get::<Players>()
+        .cloned()
+        .expect("Players missing from client data")
+}
+
+/// The key under which the user giving a command is stored.
+/// Players are separate in each server, and in direct messages.
+fn player_key(origin: impl Into<Origin>) -> String {
+    let origin = origin.into();
+    match origin.guild {
+        Some(guild) => format!("{}/{}", guild, origin.user),
+        None => format!("dm/{}", origin.user),
+    }
+}
+
+/// Get the store of every guild's settings.
+async fn guild_settings(ctx: &Context) -> Arc<Store<Settings>> {
+    let data = ctx.data.read().await;
+    data.get::<GuildSettings>()
+        .cloned()
+        .expect("GuildSettings missing from client data")
+}
+
+/// The key under which a guild's shared data is stored.
+/// In direct messages, it belongs to the user instead.
+fn guild_key(origin: impl Into<Origin>) -> String {
+    let origin = origin.into();
+    match origin.guild {
+        Some(guild) => guild.to_string(),
+        None => format!("dm/{}", origin.user),
+    }
+}
+
+/// Can the author of a message change what the bot does for the whole guild?
+/// That needs the Manage Server permission, except in direct messages.
+async fn can_manage_guild(ctx: &Context, msg: &Message) -> serenity::Result<bool> {
+    let Some(guild) = msg.guild_id else {
+        return Ok(true);
+    };
+    let permissions = guild
+        .to_partial_guild(ctx)
+        .await?
+        .member_permissions(ctx, msg.author.id)
+        .await?;
+    Ok(permissions.manage_guild())
+}
+
 /// Simple ping command to check the bot is online.
 #[command]
 async fn ping(ctx: &Context, msg: &Message) -> CommandResult {
@@ -88,109 +411,671 @@ async fn ping(ctx: &Context, msg: &Message) -> CommandResult {
 
 /// Perform an action roll.
 #[command]
-#[aliases("move", "action", "ar", "a")]
+#[aliases("action", "ar", "a")]
 async fn action_roll(ctx: &Context, msg: &Message) -> CommandResult {
-    // Parse the roll.
     let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
-    let bonus = if args.is_empty() {
-        None
-    } else {
-        let mut bonus = 0;
-        for arg in args {
-            let val = arg.parse::<InputType>();
+    make_action_roll(ctx, msg, &args, None).await
+}
+
+/// Make a move, performing its action roll.
+/// Without a move, this is just an action roll.
+#[command]
+#[aliases("move", "mv")]
+async fn move_roll(ctx: &Context, msg: &Message) -> CommandResult {
+    let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    let ruleset = ruleset(ctx, msg).await;
+    if let Some((action, rest)) = ruleset.moves().find_prefix(&args) {
+        return make_action_roll(ctx, msg, rest, Some(action)).await;
+    }
+    // Anything that isn't a number, stat, or momentum must have been meant as a move.
+    let is_roll_arg = |arg: &&str| {
+        arg.eq_ignore_ascii_case(CURSED)
+            || arg.parse::<InputType>().is_ok()
+            || arg.parse::<Stat>().is_ok()
+            || arg
+                .strip_prefix(['m', 'M'])
+                .is_some_and(|val| val.parse::<Momentum>().is_ok())
+    };
+    if let Some(arg) = args.first().filter(|arg| !is_roll_arg(arg)) {
+        msg.reply(ctx, unknown_move(arg)).await?;
+        return Ok(());
+    }
+    make_action_roll(ctx, msg, &args, None).await
+}
+
+/// Make an action roll from its arguments, for a move if given.
+async fn make_action_roll(
+    ctx: &Context,
+    msg: &Message,
+    args: &[&str],
+    action: Option<&Move>,
+) -> CommandResult {
+    // Parse the roll.
+    // Stats are named, e.g. `wits`, and momentum is given like `m5` or `m-2`;
+    // `cursed` rolls the cursed die, and everything else is a bonus.
+    let mut roll_args = ActionArgs::default();
+    for &arg in args {
+        if arg.eq_ignore_ascii_case(CURSED) {
+            roll_args.cursed = true;
+            continue;
+        }
+        if let Ok(named) = arg.parse::<Stat>() {
+            if roll_args.stat.is_some() {
+                let response = "Only one stat can be used";
+                msg.reply(ctx, response).await?;
+                return Ok(());
+            }
+            roll_args.stat = Some(named);
+            continue;
+        }
+        if let Some(val) = arg.strip_prefix(['m', 'M']) {
+            if roll_args.momentum.is_some() {
+                let response = "Momentum can only be given once";
+                msg.reply(ctx, response).await?;
+                return Ok(());
+            }
+            let val = val.parse::<Momentum>();
             match val {
-                Ok(v) => bonus += v,
-                Err(_) => {
-                    let response = format!("Invalid bonus: {}", arg);
+                Ok(v) if (MIN_MOMENTUM..=MAX_MOMENTUM).contains(&v) => roll_args.momentum = Some(v),
+                _ => {
+                    let response = format!(
+                        "Invalid momentum: {} (expected {} to {})",
+                        arg, MIN_MOMENTUM, MAX_MOMENTUM
+                    );
                     msg.reply(ctx, response).await?;
                     return Ok(());
                 }
             }
+            continue;
         }
-        Some(bonus)
-    };
+        match arg.parse::<InputType>() {
+            Ok(v) => roll_args.bonuses.push(v),
+            Err(_) => {
+                let response = format!("Invalid bonus: {}", arg);
+                msg.reply(ctx, response).await?;
+                return Ok(());
+            }
+        }
+    }
 
     // Make the roll.
-    let roll = ActionRoll::random(bonus);
-    let response = roll.to_string();
+    let response = match action_response(ctx, msg, roll_args, action).await {
+        Ok(response) => response,
+        Err(e) => {
+            msg.reply(ctx, e).await?;
+            return Ok(());
+        }
+    };
 
     // Delete the message and respond to it.
     msg.delete(ctx).await?;
-    send!(ctx, msg, response).await?;
+    send_response(ctx, msg, Response::paginated(response)).await?;
 
     Ok(())
 }
 
-/// Perform a progress roll.
+/// The arguments to an action roll.
+#[derive(Debug, Default)]
+struct ActionArgs {
+    bonuses: Vec<InputType>,
+    /// A stat to add from the character sheet.
+    stat: Option<Stat>,
+    /// The momentum to use instead of the character's.
+    momentum: Option<Momentum>,
+    cursed: bool,
+}
+
+/// Make an action roll, for a move if given, and describe it.
+async fn action_response(
+    ctx: &Context,
+    origin: impl Into<Origin>,
+    args: ActionArgs,
+    action: Option<&Move>,
+) -> Result<String, String> {
+    let origin = origin.into();
+    let character = players(ctx).await.get(&player_key(origin)).await.character;
+    let mut bonuses = args.bonuses;
+    if let Some(stat) = args.stat {
+        let Some(character) = character else {
+            return Err("You don't have a character yet; \
+set your stats with `!character set <stat> <value>`"
+                .to_string());
+        };
+        // Stats are never negative.
+        let value = character.get(Attribute::Stat(stat));
+        bonuses.push(InputType::try_from(value).unwrap_or(0));
+    }
+    let bonus = if bonuses.is_empty() {
+        None
+    } else {
+        Some(
+            limits(ctx)
+                .await
+                .total_bonus(bonuses)
+                .map_err(|e| e.to_string())?,
+        )
+    };
+
+    // Use the character's momentum unless told otherwise.
+    let momentum = args
+        .momentum
+        .or(character.map(|character| character.momentum));
+
+    let dice = challenge_dice(ctx, origin, args.cursed).await?;
+    let roll = rng(ctx).await.with(|rng| ActionRoll {
+        stat: args.stat,
+        ..ActionRoll::random(rng, dice, bonus, momentum)
+    });
+    let mut response = match action {
+        Some(action) => MoveRoll { action, roll }.to_string(),
+        None => roll.to_string(),
+    };
+    if roll.is_match() {
+        response.push_str(&match_response(ctx, origin, roll.outcome(), action).await);
+    }
+    Ok(response)
+}
+
+/// Look up moves, listing them all, searching them, or showing one in full.
 #[command]
-#[aliases("progress", "pr", "p")]
-async fn progress_roll(ctx: &Context, msg: &Message) -> CommandResult {
-    // Parse the roll.
+#[aliases("moves")]
+async fn move_reference(ctx: &Context, msg: &Message) -> CommandResult {
     let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
-    let bonus = match args.len() {
-        0 => None,
-        1 => {
-            let bonus = args[0].parse::<InputType>();
-            if bonus.is_err() {
-                let response = format!("Invalid progress: {}", args[0]);
-                msg.reply(ctx, response).await?;
-                return Ok(());
+    let ruleset = ruleset(ctx, msg).await;
+    let moves = ruleset.moves();
+    let response = match &args[..] {
+        [] => format!("Moves:\n{}", moves),
+        [subcommand, words @ ..] if subcommand.eq_ignore_ascii_case("search") => {
+            match moves.search(words)[..] {
+                [] => format!("No moves mention {}", words.join(" ")),
+                [found] => found.to_string(),
+                ref found => {
+                    let names = found.iter().map(|m| m.name.as_str()).collect::<Vec<_>>();
+                    format!("Matching moves: {}", names.join(", "))
+                }
             }
-            Some(bonus.unwrap())
         }
-        n => {
-            let response = format!("Too many arguments (expected 0 or 1, got {})", n);
-            msg.reply(ctx, response).await?;
-            return Ok(());
+        words => match moves.find(&words.join(" ")) {
+            Some(found) => found.to_string(),
+            None => unknown_move(&words.join(" ")),
+        },
+    };
+
+    send_response(ctx, msg, Response::paginated(response)).await
+}
+
+/// The dice for an action or progress roll where a command was given, with the cursed die
+/// replacing a challenge die if asked for.
+async fn challenge_dice(ctx: &Context, origin: Origin, cursed: bool) -> Result<Dice, String> {
+    let ruleset = ruleset(ctx, origin).await;
+    if cursed && !ruleset.has_cursed_die() {
+        return Err(format!("{} has no cursed die", ruleset.id()));
+    }
+    Ok(Dice {
+        cursed_die: cursed,
+        ..ruleset.dice()
+    })
+}
+
+/// What matching challenge dice mean for a roll's outcome, if known, followed by a roll
+/// on the guild's match oracle, if it has one.
+async fn match_response(
+    ctx: &Context,
+    origin: Origin,
+    outcome: Option<Outcome>,
+    action: Option<&Move>,
+) -> String {
+    let mut response = String::new();
+    if let Some(outcome) = outcome {
+        let ruleset = ruleset(ctx, origin).await;
+        let guidance = action
+            .and_then(|action| action.match_text(outcome))
+            .unwrap_or_else(|| ruleset.match_guidance(outcome));
+        response.push_str(&format!("\n**Match**: {}", guidance));
+    }
+    if let Some(names) = guild_settings(ctx)
+        .await
+        .get(&guild_key(origin))
+        .await
+        .match_oracle
+    {
+        let oracles = oracles(ctx, origin).await;
+        let words = names.split_whitespace().collect::<Vec<_>>();
+        // The tables may have been removed since they were chosen.
+        if let Ok(tables) = oracles.resolve(&words) {
+            let roll = rng(ctx)
+                .await
+                .with(|rng| TableRolls::random(rng, &oracles, &tables));
+            response.push_str(&format!("\n{}", roll));
         }
+    }
+    response
+}
+
+/// Perform a progress roll.
+#[command]
+#[aliases("progress", "pr", "p")]
+async fn progress_roll(ctx: &Context, msg: &Message) -> CommandResult {
+    // Parse the roll.
+    // A single number is the progress score; anything else names a track.
+    // Either may follow `cursed` to roll the cursed die.
+    let mut args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    let cursed = args
+        .first()
+        .is_some_and(|arg| arg.eq_ignore_ascii_case(CURSED));
+    if cursed {
+        args.remove(0);
+    }
+    let progress = if args.is_empty() {
+        Progress::Unknown
+    } else if let (1, Ok(score)) = (args.len(), args[0].parse::<InputType>()) {
+        Progress::Score(score)
+    } else {
+        Progress::Track(args.join(" "))
     };
 
     // Make the roll.
-    let roll = ProgressRoll::random(bonus);
-    let response = roll.to_string();
+    let response = match progress_response(ctx, msg, progress, cursed).await {
+        Ok(response) => response,
+        Err(e) => {
+            msg.reply(ctx, e).await?;
+            return Ok(());
+        }
+    };
 
     // Delete the message and respond to it.
     msg.delete(ctx).await?;
-    send!(ctx, msg, response).await?;
+    send_response(ctx, msg, Response::paginated(response)).await?;
 
     Ok(())
 }
 
-/// Perform an oracle roll.
+/// What a progress roll is made for.
+#[derive(Debug, PartialEq, Eq)]
+enum Progress {
+    /// No score was given, so the outcome is left to the player.
+    Unknown,
+    Score(InputType),
+    /// One of the player's progress tracks, by name.
+    Track(String),
+}
+
+/// Make a progress roll and describe it.
+/// Rolling on a track completes it with a hit, marking any legacy reward.
+async fn progress_response(
+    ctx: &Context,
+    origin: impl Into<Origin>,
+    progress: Progress,
+    cursed: bool,
+) -> Result<String, String> {
+    let origin = origin.into();
+    let players = players(ctx).await;
+    let key = player_key(origin);
+    let (bonus, track) = match progress {
+        Progress::Unknown => (None, None),
+        Progress::Score(score) => (Some(score), None),
+        Progress::Track(name) => {
+            let player = players.get(&key).await;
+            match tracks::find(&player.tracks, &name) {
+                Some(i) => (Some(player.tracks[i].score()), Some(name)),
+                None => return Err(format!("No progress track named {}", name)),
+            }
+        }
+    };
+
+    let dice = challenge_dice(ctx, origin, cursed).await?;
+    let roll = rng(ctx)
+        .await
+        .with(|rng| ProgressRoll::random(rng, dice, bonus));
+    let response = match (track, roll.outcome()) {
+        (Some(name), Some(outcome)) => players
+            .update(&key, |player| {
+                let Some(i) = tracks::find(&player.tracks, &name) else {
+                    // The track was removed while we were rolling.
+                    return roll.to_string();
+                };
+                let reward = player.complete_track(i, outcome);
+                let mut response = format!("{}\n{}", player.tracks[i], roll);
+                if let Some((kind, ticks)) = reward.filter(|&(_, ticks)| ticks > 0) {
+                    response.push_str(&format!(
+                        "\nMarked {} legacy ticks on {}: {}",
+                        ticks,
+                        kind,
+                        player.legacy.track(kind)
+                    ));
+                }
+                response
+            })
+            .await
+            .map_err(|e| format!("Error saving your progress tracks: {}", e))?,
+        _ => roll.to_string(),
+    };
+    if roll.is_match() {
+        Ok(response + &match_response(ctx, origin, roll.outcome(), None).await)
+    } else {
+        Ok(response)
+    }
+}
+
+/// Perform an oracle roll, either on named tables or as bare d100s.
 #[command]
 #[aliases("oracle", "or", "o")]
 async fn oracle_roll(ctx: &Context, msg: &Message) -> CommandResult {
     // Parse the roll.
     let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
-    let num_rolls = match args.len() {
-        0 => 1,
-        1 => {
-            let num_rolls = args[0].parse::<InputType>();
+    let num_rolls = match args[..] {
+        [] => 1,
+        [arg] if arg.eq_ignore_ascii_case("list") => {
+            msg.reply(ctx, oracle_list(ctx, msg).await).await?;
+            return Ok(());
+        }
+        [subcommand, ..] if subcommand.eq_ignore_ascii_case("import") => {
+            return oracle_import(ctx, msg, &args[1..]).await;
+        }
+        [subcommand, ..] if subcommand.eq_ignore_ascii_case("remove") => {
+            return oracle_remove(ctx, msg, &args[1..].join(" ")).await;
+        }
+        [arg] if arg.starts_with(|c: char| c.is_ascii_digit()) => {
+            let num_rolls = arg.parse::<InputType>();
             if num_rolls.is_err() {
-                let response = format!("Invalid number of rolls: {}", args[0]);
+                let response = format!("Invalid number of rolls: {}", arg);
                 msg.reply(ctx, response).await?;
                 return Ok(());
             }
             num_rolls.unwrap()
         }
-        n => {
-            let response = format!("Too many arguments (expected 0 or 1, got {})", n);
-            msg.reply(ctx, response).await?;
+        _ => return oracle_table_roll(ctx, msg, &args).await,
+    };
+
+    // Make the roll.
+    let response = match bare_oracle_response(ctx, num_rolls).await {
+        Ok(response) => response,
+        Err(e) => {
+            msg.reply(ctx, e).await?;
+            return Ok(());
+        }
+    };
+
+    // Delete the message and respond to it.
+    msg.delete(ctx).await?;
+    send_response(ctx, msg, response).await?;
+
+    Ok(())
+}
+
+/// Roll on the oracle tables named by the given words.
+async fn oracle_table_roll(ctx: &Context, msg: &Message, words: &[&str]) -> CommandResult {
+    let response = match oracle_table_response(ctx, msg, words).await {
+        Ok(response) => response,
+        Err(e) => {
+            msg.reply(ctx, e).await?;
             return Ok(());
         }
     };
 
+    // Delete the message and respond to it.
+    msg.delete(ctx).await?;
+    send_response(ctx, msg, response).await?;
+
+    Ok(())
+}
+
+/// List the oracle tables available where a command was given.
+async fn oracle_list(ctx: &Context, origin: impl Into<Origin>) -> String {
+    format!("Oracle tables:\n{}", oracles(ctx, origin).await)
+}
+
+/// Roll some bare d100s.
+async fn bare_oracle_response(ctx: &Context, num_rolls: InputType) -> Result<Response, String> {
+    limits(ctx)
+        .await
+        .check_oracle_rolls(num_rolls)
+        .map_err(|e| e.to_string())?;
+    let roll = rng(ctx)
+        .await
+        .with(|rng| OracleRoll::random(rng, num_rolls.into()));
+    Ok(Response::paginated(roll.to_string()))
+}
+
+/// Roll on the oracle tables named by the given words.
+async fn oracle_table_response(
+    ctx: &Context,
+    origin: impl Into<Origin>,
+    words: &[&str],
+) -> Result<Response, String> {
+    let oracles = oracles(ctx, origin).await;
+    let tables = oracles.resolve(words).map_err(|word| {
+        format!(
+            "Unknown oracle table: {} (use `!oracle list` to see them all)",
+            word
+        )
+    })?;
+    let roll = rng(ctx)
+        .await
+        .with(|rng| TableRolls::random(rng, &oracles, &tables));
+    Ok(Response::paginated(roll.to_string()))
+}
+
+/// Ask the oracle a yes/no question.
+#[command]
+#[aliases("ask")]
+async fn ask_oracle(ctx: &Context, msg: &Message) -> CommandResult {
+    // Parse the odds, which may be several words.
+    let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    let odds = if args.is_empty() {
+        Odds::FiftyFifty
+    } else {
+        match args.join(" ").parse::<Odds>() {
+            Ok(odds) => odds,
+            Err(()) => {
+                let response = format!(
+                    "Invalid odds: {} (expected almost certain, likely, 50/50, \
+unlikely, or small chance)",
+                    args.join(" ")
+                );
+                msg.reply(ctx, response).await?;
+                return Ok(());
+            }
+        }
+    };
+
     // Make the roll.
-    let roll = OracleRoll::random(num_rolls.into());
-    let response = roll.to_string();
+    let roll = rng(ctx).await.with(|rng| AskRoll::random(rng, odds));
 
     // Delete the message and respond to it.
     msg.delete(ctx).await?;
-    send!(ctx, msg, response).await?;
+    send!(ctx, msg, roll.to_string()).await?;
 
     Ok(())
 }
 
+/// Work out the exact chances of each outcome of an action or progress roll.
+#[command]
+async fn odds(ctx: &Context, msg: &Message) -> CommandResult {
+    let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    let dice = ruleset(ctx, msg).await.dice();
+    let response = match outcome_chances(&args, dice, limits(ctx).await) {
+        Ok(chances) => chances.to_string(),
+        Err(e) => e,
+    };
+    msg.reply(ctx, response).await?;
+    Ok(())
+}
+
+/// Parse the arguments to `odds` and work out the chances they ask for.
+fn outcome_chances(args: &[&str], dice: Dice, limits: Limits) -> Result<OutcomeChances, String> {
+    const USAGE: &str = "Expected `action <bonus>`, optionally with momentum like `m5`, \
+or `progress <score>`";
+
+    match args {
+        [kind, rest @ ..] if kind.eq_ignore_ascii_case("action") && !rest.is_empty() => {
+            let mut bonuses = Vec::with_capacity(rest.len());
+            let mut momentum = None;
+            for arg in rest {
+                if let Some(val) = arg.strip_prefix(['m', 'M']) {
+                    match val.parse::<Momentum>() {
+                        Ok(v)
+                            if (MIN_MOMENTUM..=MAX_MOMENTUM).contains(&v) && momentum.is_none() =>
+                        {
+                            momentum = Some(v)
+                        }
+                        _ => {
+                            return Err(format!(
+                                "Invalid momentum: {} (expected it once, from {} to {})",
+                                arg, MIN_MOMENTUM, MAX_MOMENTUM
+                            ))
+                        }
+                    }
+                } else {
+                    let bonus = arg
+                        .parse::<InputType>()
+                        .map_err(|_| format!("Invalid bonus: {}", arg))?;
+                    bonuses.push(bonus);
+                }
+            }
+            if bonuses.is_empty() {
+                return Err(USAGE.to_string());
+            }
+            let bonus = limits.total_bonus(bonuses).map_err(|e| e.to_string())?;
+            Ok(OutcomeChances::action(dice, bonus, momentum))
+        }
+        [kind, score] if kind.eq_ignore_ascii_case("progress") => {
+            let score = score
+                .parse::<InputType>()
+                .map_err(|_| format!("Invalid progress score: {}", score))?;
+            Ok(OutcomeChances::progress(dice, score))
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Import a custom oracle table for the guild from an attached file.
+async fn oracle_import(ctx: &Context, msg: &Message, args: &[&str]) -> CommandResult {
+    // Custom tables are found before the bundled ones, so they're for the guild to manage.
+    if !can_manage_guild(ctx, msg).await? {
+        msg.reply(ctx, MANAGE_GUILD_ERROR).await?;
+        return Ok(());
+    }
+    let Some(attachment) = msg.attachments.first() else {
+        let response = "Attach a CSV, JSON, or Markdown file of the table, \
+and optionally give its die and name, like `!oracle import d20 Weather`";
+        msg.reply(ctx, response).await?;
+        return Ok(());
+    };
+    let Some(format) = Format::of(&attachment.filename) else {
+        let response = format!(
+            "Can't import {}: only CSV, JSON, and Markdown files are supported",
+            attachment.filename
+        );
+        msg.reply(ctx, response).await?;
+        return Ok(());
+    };
+    if attachment.size > MAX_IMPORT_SIZE {
+        let response = format!(
+            "Can't import {}: files can be at most {} KB",
+            attachment.filename,
+            MAX_IMPORT_SIZE / 1000
+        );
+        msg.reply(ctx, response).await?;
+        return Ok(());
+    }
+
+    // The die is optional, and the name defaults to the file's.
+    let mut words = args;
+    let mut sides = None;
+    if let Some(die) = words
+        .first()
+        .and_then(|word| word.strip_prefix(['d', 'D']))
+        .and_then(|die| die.parse().ok())
+    {
+        sides = Some(die);
+        words = &words[1..];
+    }
+    let name = match words {
+        [] => attachment
+            .filename
+            .rsplit_once('.')
+            .map_or(attachment.filename.as_str(), |(stem, _)| stem)
+            .to_string(),
+        words => words.join(" "),
+    };
+    // Names that look like other arguments couldn't be rolled on.
+    let reserved = ["list", "import", "remove"];
+    if name.is_empty()
+        || name.starts_with(|c: char| c.is_ascii_digit())
+        || reserved.iter().any(|word| name.eq_ignore_ascii_case(word))
+    {
+        let response = format!("Can't use `{}` as the name of a table", name);
+        msg.reply(ctx, response).await?;
+        return Ok(());
+    }
+
+    // Read and check the table.
+    let Ok(contents) = String::from_utf8(attachment.download().await?) else {
+        let response = format!("Can't import {}: it isn't text", attachment.filename);
+        msg.reply(ctx, response).await?;
+        return Ok(());
+    };
+    let table = match table_import::import(name, format, &contents, sides) {
+        Ok(table) => table,
+        Err(e) => {
+            msg.reply(ctx, e.to_string()).await?;
+            return Ok(());
+        }
+    };
+    if let Err(e) = limits(ctx).await.check_sides(table.sides()) {
+        msg.reply(ctx, e.to_string()).await?;
+        return Ok(());
+    }
+    if let Err(e) = oracles(ctx, msg).await.check_references(&table) {
+        msg.reply(ctx, format!("Invalid table: {}", e)).await?;
+        return Ok(());
+    }
+
+    // Save the table, replacing any with the same name.
+    let response = format!(
+        "Imported **{}** (d{}, {} rows); roll on it with `!oracle {}`",
+        table.name,
+        table.sides(),
+        table.rows.len(),
+        table.name
+    );
+    custom_oracles(ctx)
+        .await
+        .update(&guild_key(msg), |tables| {
+            tables.retain(|t| !t.name.eq_ignore_ascii_case(&table.name));
+            tables.push(table);
+        })
+        .await?;
+
+    msg.reply(ctx, response).await?;
+    Ok(())
+}
+
+/// Remove one of the guild's custom oracle tables.
+async fn oracle_remove(ctx: &Context, msg: &Message, name: &str) -> CommandResult {
+    if !can_manage_guild(ctx, msg).await? {
+        msg.reply(ctx, MANAGE_GUILD_ERROR).await?;
+        return Ok(());
+    }
+    let removed = custom_oracles(ctx)
+        .await
+        .update(&guild_key(msg), |tables| {
+            let before = tables.len();
+            tables.retain(|table| !table.name.eq_ignore_ascii_case(name));
+            tables.len() < before
+        })
+        .await?;
+    let response = if removed {
+        format!("Removed the custom table **{}**", name)
+    } else {
+        format!("There is no custom table called {}", name)
+    };
+
+    msg.reply(ctx, response).await?;
+    Ok(())
+}
+
 /// Perform a custom roll.
 #[command]
 #[aliases("roll", "r")]
@@ -206,25 +1091,389 @@ async fn custom_roll(ctx: &Context, msg: &Message) -> CommandResult {
             return Ok(());
         }
     };
-    let spec = if let Ok(spec) = spec_raw.parse() {
-        spec
-    } else {
-        let response = "Invalid roll specification";
-        msg.reply(ctx, response).await?;
-        return Ok(());
-    };
 
     // Make the roll.
-    let roll = CustomRoll::random(spec);
-    let response = roll.to_string();
+    let response = match custom_roll_response(ctx, spec_raw).await {
+        Ok(response) => response,
+        Err(e) => {
+            msg.reply(ctx, e).await?;
+            return Ok(());
+        }
+    };
 
     // Delete the message and respond to it.
     msg.delete(ctx).await?;
-    send!(ctx, msg, response).await?;
+    send_response(ctx, msg, response).await?;
 
     Ok(())
 }
 
+/// Work out the exact distribution of a custom roll's total.
+#[command]
+#[aliases("distribution")]
+async fn dist(ctx: &Context, msg: &Message) -> CommandResult {
+    // Parse the roll, exactly as for a custom roll.
+    let Some((_, spec_raw)) = msg.content.split_once(' ') else {
+        let response = "Not enough arguments (expected 1+, got 0)";
+        msg.reply(ctx, response).await?;
+        return Ok(());
+    };
+    let response = match roll_spec(ctx, spec_raw.trim()).await {
+        // Working out a distribution can take a while, so keep it off the async workers.
+        Ok(spec) => match tokio::task::spawn_blocking(move || Distribution::of(spec)).await? {
+            Ok(distribution) => Response::summarised(
+                distribution.to_string(),
+                distribution.summary(),
+                "distribution.txt",
+            ),
+            Err(e) => Response::paginated(e.to_string()),
+        },
+        Err(e) => Response::paginated(e),
+    };
+    send_response(ctx, msg, response).await
+}
+
+/// Parse a custom roll, checking it against the limits.
+async fn roll_spec(ctx: &Context, spec_raw: &str) -> Result<RollSpec, String> {
+    let spec = spec_raw
+        .parse::<RollSpec>()
+        .map_err(|e| format!("Invalid roll specification: {}", e.describe(spec_raw)))?;
+    limits(ctx)
+        .await
+        .check_spec(&spec)
+        .map_err(|e| e.to_string())?;
+    Ok(spec)
+}
+
+/// Make a custom roll and describe it.
+/// Large rolls are summarised, with the full breakdown attached.
+async fn custom_roll_response(ctx: &Context, spec_raw: &str) -> Result<Response, String> {
+    let spec = roll_spec(ctx, spec_raw).await?;
+    let roll = rng(ctx).await.with(|rng| CustomRoll::random(rng, spec));
+    Ok(Response::summarised(
+        roll.to_string(),
+        roll.summary(),
+        "roll.txt",
+    ))
+}
+
+/// Manage progress tracks.
+#[command]
+#[aliases("tracks", "t")]
+async fn track(ctx: &Context, msg: &Message) -> CommandResult {
+    const USAGE: &str = "Expected `new <kind> <rank> <name>`, `mark <name>`, \
+`remove <name>`, or `list`";
+
+    let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    let players = players(ctx).await;
+    let key = player_key(msg);
+    // Listing the tracks is the default.
+    let (subcommand, rest) = match args.split_first() {
+        Some((first, rest)) => (first.to_ascii_lowercase(), rest),
+        None => ("list".to_string(), &[][..]),
+    };
+    let response = match (subcommand.as_str(), rest) {
+        ("list", []) => {
+            let tracks = players.get(&key).await.tracks;
+            if tracks.is_empty() {
+                "You have no progress tracks".to_string()
+            } else {
+                tracks
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        }
+        ("new", [kind, rank, name @ ..]) if !name.is_empty() => {
+            let (kind, rank) = match (kind.parse::<TrackKind>(), rank.parse::<Rank>()) {
+                (Ok(kind), Ok(rank)) => (kind, rank),
+                (Err(_), _) => {
+                    let response = format!(
+                        "Invalid track kind: {} (expected vow, expedition, combat, or connection)",
+                        kind
+                    );
+                    msg.reply(ctx, response).await?;
+                    return Ok(());
+                }
+                (_, Err(_)) => {
+                    let response = format!(
+                        "Invalid rank: {} (expected troublesome, dangerous, formidable, \
+extreme, or epic)",
+                        rank
+                    );
+                    msg.reply(ctx, response).await?;
+                    return Ok(());
+                }
+            };
+            let track = ProgressTrack::new(name.join(" "), kind, rank);
+            players
+                .update(&key, |player| {
+                    if tracks::find(&player.tracks, &track.name).is_some() {
+                        format!("You already have a track named {}", track.name)
+                    } else {
+                        let response = format!("New track: {}", track);
+                        player.tracks.push(track);
+                        response
+                    }
+                })
+                .await?
+        }
+        ("mark", name) if !name.is_empty() => {
+            let name = name.join(" ");
+            players
+                .update(&key, |player| match tracks::find(&player.tracks, &name) {
+                    Some(i) => {
+                        let track = &mut player.tracks[i];
+                        track.mark(1);
+                        format!("Marked progress: {}", track)
+                    }
+                    None => format!("No progress track named {}", name),
+                })
+                .await?
+        }
+        ("remove", name) if !name.is_empty() => {
+            let name = name.join(" ");
+            players
+                .update(&key, |player| match tracks::find(&player.tracks, &name) {
+                    Some(i) => format!("Removed track: {}", player.tracks.remove(i)),
+                    None => format!("No progress track named {}", name),
+                })
+                .await?
+        }
+        _ => USAGE.to_string(),
+    };
+
+    msg.reply(ctx, response).await?;
+    Ok(())
+}
+
+/// Show legacy tracks and experience, mark or spend them, or make a continuity roll.
+#[command]
+#[aliases("legacies", "l")]
+async fn legacy(ctx: &Context, msg: &Message) -> CommandResult {
+    const USAGE: &str = "Expected `<track>` to roll, `mark <track> <ticks>`, `spend <xp>`, \
+or nothing to show your legacy; the tracks are quests, bonds, and discoveries";
+
+    let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    let players = players(ctx).await;
+    let key = player_key(msg);
+    let args = args
+        .iter()
+        .map(|arg| arg.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let response = match args[..] {
+        [] => players.get(&key).await.legacy.to_string(),
+        ["mark", kind, ticks] => match (kind.parse::<LegacyKind>(), ticks.parse::<u16>()) {
+            (Ok(kind), Ok(ticks)) => {
+                players
+                    .update(&key, |player| {
+                        let track = player.legacy.track_mut(kind);
+                        track.mark(ticks);
+                        format!("**{}**: {}", kind, track)
+                    })
+                    .await?
+            }
+            _ => USAGE.to_string(),
+        },
+        ["spend", xp] => match xp.parse::<u16>() {
+            Ok(xp) => {
+                players
+                    .update(&key, |player| {
+                        if player.legacy.spend(xp) {
+                            format!("Spent {} XP; {} left", xp, player.legacy.xp_available())
+                        } else {
+                            format!(
+                                "Not enough XP (you have {} available)",
+                                player.legacy.xp_available()
+                            )
+                        }
+                    })
+                    .await?
+            }
+            Err(_) => USAGE.to_string(),
+        },
+        [kind] => match kind.parse::<LegacyKind>() {
+            Ok(kind) => {
+                // A continuity roll is a progress roll against the legacy track.
+                let track = *players.get(&key).await.legacy.track(kind);
+                let dice = ruleset(ctx, msg).await.dice();
+                let roll = rng(ctx)
+                    .await
+                    .with(|rng| ProgressRoll::random(rng, dice, track.score()));
+                let response = format!("**{}**: {}\n{}", kind, track, roll);
+                msg.delete(ctx).await?;
+                send!(ctx, msg, response).await?;
+                return Ok(());
+            }
+            Err(_) => USAGE.to_string(),
+        },
+        _ => USAGE.to_string(),
+    };
+
+    msg.reply(ctx, response).await?;
+    Ok(())
+}
+
+/// Show or change the player's character sheet.
+#[command]
+#[aliases("char", "sheet", "c")]
+async fn character(ctx: &Context, msg: &Message) -> CommandResult {
+    const USAGE: &str = "Expected `set <name> <value>`, `add <name> <amount>`, \
+or nothing to show your character; the names are edge, heart, iron, shadow, wits, \
+health, spirit, supply, and momentum";
+
+    let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    let players = players(ctx).await;
+    let key = player_key(msg);
+    let subcommand = args.first().map(|arg| arg.to_ascii_lowercase());
+    let response = match (subcommand.as_deref(), &args[..]) {
+        (None, _) => match players.get(&key).await.character {
+            Some(character) => character.to_string(),
+            None => "You don't have a character yet; \
+set your stats with `!character set <stat> <value>`"
+                .to_string(),
+        },
+        (Some(subcommand @ ("set" | "add")), [_, name, value]) => {
+            match (name.parse::<Attribute>(), value.parse::<i8>()) {
+                (Ok(attribute), Ok(value)) => {
+                    players
+                        .update(&key, |player| {
+                            // The first change creates the character.
+                            let character = player.character.get_or_insert_with(Default::default);
+                            if subcommand == "add" {
+                                character.adjust(attribute, value);
+                            } else if !character.set(attribute, value) {
+                                let range = attribute.range();
+                                return format!(
+                                    "Invalid {}: {} (expected {} to {})",
+                                    attribute,
+                                    value,
+                                    range.start(),
+                                    range.end()
+                                );
+                            }
+                            format!("{} is now {}", attribute, character.get(attribute))
+                        })
+                        .await?
+                }
+                _ => USAGE.to_string(),
+            }
+        }
+        _ => USAGE.to_string(),
+    };
+
+    msg.reply(ctx, response).await?;
+    Ok(())
+}
+
+/// Show or change how the bot behaves in this server.
+#[command]
+#[aliases("config")]
+async fn settings(ctx: &Context, msg: &Message) -> CommandResult {
+    const USAGE: &str = "Expected `match-oracle <tables>`, `match-oracle off`, \
+`ruleset <name>`, `channel-ruleset <name>`, `channel-ruleset default`, \
+or nothing to show the settings";
+
+    let args = msg.content.split_whitespace().skip(1).collect::<Vec<_>>();
+    // Anyone can see the settings, but changing them is up to those who manage the server.
+    if !args.is_empty() && !can_manage_guild(ctx, msg).await? {
+        msg.reply(ctx, MANAGE_GUILD_ERROR).await?;
+        return Ok(());
+    }
+    let settings = guild_settings(ctx).await;
+    let key = guild_key(msg);
+    let response = match &args[..] {
+        [] => settings.get(&key).await.to_string(),
+        [setting, words @ ..] if setting.eq_ignore_ascii_case("match-oracle") => match words {
+            [] => USAGE.to_string(),
+            [off] if off.eq_ignore_ascii_case("off") => {
+                settings
+                    .update(&key, |settings| settings.match_oracle = None)
+                    .await?;
+                "Matches will no longer roll an oracle".to_string()
+            }
+            words => match oracles(ctx, msg).await.resolve(words) {
+                Ok(tables) => {
+                    let names = tables
+                        .iter()
+                        .map(|table| table.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let response = format!("Matches will now roll on {}", names);
+                    settings
+                        .update(&key, |settings| settings.match_oracle = Some(names))
+                        .await?;
+                    response
+                }
+                Err(word) => format!(
+                    "Unknown oracle table: {} (use `!oracle list` to see them all)",
+                    word
+                ),
+            },
+        },
+        [setting, name] if setting.eq_ignore_ascii_case("ruleset") => match name.parse() {
+            Ok(ruleset) => {
+                settings
+                    .update(&key, |settings| settings.ruleset = ruleset)
+                    .await?;
+                format!("This server now plays {}", ruleset)
+            }
+            Err(()) => unknown_ruleset(name),
+        },
+        [setting, name] if setting.eq_ignore_ascii_case("channel-ruleset") => {
+            let channel = msg.channel_id.0;
+            if name.eq_ignore_ascii_case("default") {
+                let ruleset = settings
+                    .update(&key, |settings| {
+                        settings.channel_rulesets.remove(&channel);
+                        settings.ruleset
+                    })
+                    .await?;
+                format!("This channel now plays the server's ruleset, {}", ruleset)
+            } else {
+                match name.parse() {
+                    Ok(ruleset) => {
+                        settings
+                            .update(&key, |settings| {
+                                settings.channel_rulesets.insert(channel, ruleset)
+                            })
+                            .await?;
+                        format!("This channel now plays {}", ruleset)
+                    }
+                    Err(()) => unknown_ruleset(name),
+                }
+            }
+        }
+        _ => USAGE.to_string(),
+    };
+
+    msg.reply(ctx, response).await?;
+    Ok(())
+}
+
+/// The reply to an unknown ruleset name, listing the known ones.
+fn unknown_ruleset(name: &str) -> String {
+    let names = RulesetId::ALL
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    format!(
+        "Unknown ruleset: {} (expected one of {})",
+        name,
+        names.join(", ")
+    )
+}
+
+/// The reply to an unknown move name.
+fn unknown_move(name: &str) -> String {
+    format!(
+        "Unknown move: {} (moves are named as in the rulebook, like Face Danger)",
+        name
+    )
+}
+
 /// Provide the channel contents as a file to download.
 #[command]
 async fn download(ctx: &Context, msg: &Message) -> CommandResult {
@@ -236,9 +1485,6 @@ async fn download(ctx: &Context, msg: &Message) -> CommandResult {
     }
     #[cfg(feature = "download")]
     {
-        use chrono::Utc;
-        use serenity::futures::StreamExt;
-
         // Check arguments.
         if msg.content.split_whitespace().count() > 1 {
             let response = "Unexpected argument(s)";
@@ -246,27 +1492,7 @@ async fn download(ctx: &Context, msg: &Message) -> CommandResult {
             return Ok(());
         }
 
-        // Read all the past messages.
-        let mut all_messages = Vec::new();
-        let mut message_stream = msg.channel_id.messages_iter(ctx).boxed();
-        while let Some(message) = message_stream.next().await {
-            let message = message?;
-            // Ignore empty messages and this command itself.
-            if !(message.content.is_empty() || message.content.trim().ends_with("download")) {
-                all_messages.push(message.content);
-            }
-        }
-
-        // Combine them into a single buffer.
-        all_messages.reverse();
-        let response = all_messages.join("\n");
-        let channel_name = msg
-            .channel_id
-            .name(ctx)
-            .await
-            .unwrap_or_else(|| "channel".to_string());
-        let now = Utc::now().format("%Y-%m-%d-%H-%M-%S");
-        let filename = format!("{}-{}.txt", channel_name, now);
+        let (response, filename) = channel_history(ctx, msg.channel_id).await?;
 
         // Delete the message and respond to it.
         msg.delete(ctx).await?;
@@ -280,6 +1506,36 @@ async fn download(ctx: &Context, msg: &Message) -> CommandResult {
     }
 }
 
+/// Read the entire history of a channel into a single buffer, along with a name for the file
+/// to download it as.
+#[cfg(feature = "download")]
+async fn channel_history(ctx: &Context, channel: ChannelId) -> serenity::Result<(String, String)> {
+    use chrono::Utc;
+    use serenity::futures::StreamExt;
+
+    // Read all the past messages.
+    let mut all_messages = Vec::new();
+    let mut message_stream = channel.messages_iter(ctx).boxed();
+    while let Some(message) = message_stream.next().await {
+        let message = message?;
+        // Ignore empty messages and this command itself.
+        if !(message.content.is_empty() || message.content.trim().ends_with("download")) {
+            all_messages.push(message.content);
+        }
+    }
+
+    // Combine them into a single buffer.
+    all_messages.reverse();
+    let history = all_messages.join("\n");
+    let channel_name = channel
+        .name(ctx)
+        .await
+        .unwrap_or_else(|| "channel".to_string());
+    let now = Utc::now().format("%Y-%m-%d-%H-%M-%S");
+    let filename = format!("{}-{}.txt", channel_name, now);
+    Ok((history, filename))
+}
+
 /// Display a help message.
 #[command]
 #[aliases("h")]
@@ -287,43 +1543,125 @@ async fn help(ctx: &Context, msg: &Message) -> CommandResult {
     const HELP_TEXT: &str = "***Starforged Bot Guide***
 *This bot helps you make all the rolls you need. It supports the following commands:*
 
-Action Rolls (`!move`, `!action`, `!ar`, `!a`):
+Action Rolls (`!action`, `!ar`, `!a`):
    Roll an action d6 against the challenge 2d10.
    Optionally specify a list of bonuses (i.e. stats and adds); \
 this will calculate your total score and tell you the outcome.
-   Example: `!action 3 2`
+   Name a stat to use it from your character sheet, along with your momentum.
+   Add your momentum like `m5` to find out whether burning it would help; \
+negative momentum cancels a matching action die.
+   Matching challenge dice come with a prompt for what the match means.
+   In Sundered Isles, add `cursed` to roll the cursed die in place of a challenge die; \
+a 10 on it is a cursed result.
+   Example: `!action 3 2`, `!action 2 m-2`, `!action wits +1`, `!action iron cursed`
+
+Moves (`!move`, `!mv`):
+   Make an action roll for a move, which also tells you what happens on the outcome.
+   Give the move's name, then anything you'd give an action roll.
+   Example: `!move face-danger edge`, `!move gather information wits 1`
+
+Move Reference (`!moves`):
+   List all the moves, show one in full, or use `search <text>` to find one.
+   Example: `!moves`, `!moves strike`, `!moves search injury`
 
 Progress Rolls (`!progress`, `!pr`, `!p`):
    Roll your progress against the challenge 2d10.
    Optionally specify your progress amount (i.e. the number of \
-filled boxes); this will tell you the outcome.
-   Example: `!p 9`
+filled boxes) or the name of one of your progress tracks; this will tell you the outcome.
+   In Sundered Isles, start with `cursed` to roll the cursed die.
+   Example: `!p 9`, `!p Find the lost ship`, `!p cursed 7`
+
+Odds (`!odds`):
+   Work out the exact chances of a strong hit, weak hit, miss, and match \
+before you roll, to see whether it's worth pushing your luck.
+   Use `action <bonus>` or `progress <score>`. Add your momentum like `m5` to an action roll \
+to count burning it whenever that helps.
+   Example: `!odds action 3`, `!odds action 2 m6`, `!odds progress 7`
+
+Progress Tracks (`!track`, `!tracks`, `!t`):
+   Keep track of your vows, expeditions, combats, and connections.
+   Use `new <kind> <rank> <name>` to start a track, `mark <name>` to mark progress \
+according to its rank, `remove <name>` to remove it, or `list` to show all your tracks.
+   Rolling progress on a track completes it with a hit, marking the reward on your legacy.
+   Example: `!track new vow dangerous Find the lost ship`, `!track mark Find the lost ship`
+
+Legacy (`!legacy`, `!l`):
+   Show your quests, bonds, and discoveries legacy tracks and your XP.
+   Give a track's name to make a continuity roll with it, `mark <track> <ticks>` to mark it \
+yourself, or `spend <xp>` to spend experience.
+   Example: `!legacy`, `!legacy bonds`, `!legacy spend 3`
+
+Character Sheets (`!character`, `!char`, `!c`):
+   Show your stats, meters, and momentum.
+   Use `set <name> <value>` to set one, or `add <name> <amount>` to adjust it.
+   Example: `!char set wits 3`, `!char add health -2`
 
 Oracle Rolls (`!oracle`, `!or`, `!o`):
    Roll a d100 to pick from an oracle table.
-   You may specify a number to roll multiple oracles at once.
-   Example: `!oracle 3`
+   Name one or more tables to look up the result, or use `list` to see them all.
+   Results that ask for more rolls, such as rolling twice, are rolled too.
+   You may specify a number to roll multiple bare d100s at once.
+   Example: `!oracle action theme`, `!oracle planet type`, `!oracle 3`
+   Use `import` with an attached CSV, JSON, or Markdown table to add your own for the server, \
+optionally giving the die it uses and its name; use `remove <name>` to remove it again. \
+Both need the Manage Server permission.
+   Example: `!oracle import d20 Weather`, `!oracle remove Weather`
+
+Ask the Oracle (`!ask`):
+   Roll a d100 to answer a yes/no question, given the odds of a yes: \
+almost certain, likely, 50/50 (the default), unlikely, or small chance.
+   Matching digits mean an extreme result or a twist.
+   Example: `!ask likely`, `!ask small chance`
 
 Custom rolls (`!roll`, `!r`):
    Roll any dice and bonuses you want, using the format `XdY + Z`.
-   You may specify multiple dice and multiple bonuses.
-   Example: `!r 2d4 + 1 + d6 + 4d10`
+   You may combine any number of dice and numbers using `+`, `-`, `*`, `/`, \
+and parentheses, as well as the functions `max`, `min`, `floor`, `ceil`, `round`, and `abs`.
+   Add `khN`/`klN` to keep only the highest/lowest N dice, or `dhN`/`dlN` \
+to drop the highest/lowest N dice.
+   Add `!` to explode dice on their maximum (or `!>N` to explode on N or \
+higher), `rN` to reroll dice showing N or lower, or `roN` to reroll them only once.
+   Add `>=N` (or `<=N`) to count successes at or above (or below) N instead \
+of adding up the dice, and `fN` to subtract a failure for each die showing N or lower.
+   Example: `!r 2d4 + 1 + d6 + 4d10 - 2`, `!r (2d6 + 3) * 2`, `!r floor(d6 / 2)`, \
+`!r 4d6kh3`, `!r 3d6r1!`, `!r 8d6>=4f1`
+
+Distributions (`!dist`):
+   Work out the exact chances of every total of a custom roll, along with its minimum, \
+maximum, mean, and standard deviation, and show them as a histogram.
+   Takes the same format as `!roll`, except that exploding dice can't also be kept or dropped.
+   Example: `!dist 4d6kh3`, `!dist 2d10 + 3`, `!dist 6d6>=5`
 
-Note that all numbers are limited to 255, i.e. you cannot roll 2d1000 \
-or ask for 300 oracle rolls.
+Note that there are limits on how many dice and oracles you can roll at once, \
+how many sides dice can have, and how large bonuses can be.
 
 *Other commands:*
 
+Settings (`!settings`, `!config`):
+   Show or change how the bot behaves in this server.
+   Use `match-oracle <tables>` to roll on oracle tables whenever the challenge dice match, \
+or `match-oracle off` to stop.
+   Use `ruleset <name>` to choose the moves and oracles for the server: Starforged (the default), \
+Ironsworn Classic, or Sundered Isles. Use `channel-ruleset <name>` to choose them for just this \
+channel, or `channel-ruleset default` to go back to the server's.
+   Changing settings needs the Manage Server permission.
+   Example: `!settings match-oracle pay the price`, `!settings ruleset classic`
+
 Help (`!help`, `!h`):
    Display this message.
 
 Download (`!download`):
    Download the entire history of the channel as a text file.
-   This file is created transiently upon request and is not stored by the bot.";
+   This file is created transiently upon request and is not stored by the bot.
+
+Slash Commands (`/action`, `/progress`, `/oracle`, `/roll`, `/download`, `/help`):
+   These work just like the commands above, with Discord prompting you for each option.
+   They work even in servers where the bot can't read messages; use `/help` to see their options.
+   Example: `/action bonus:2 stat:Wits`, `/progress track:Find the lost ship`";
 
     // Delete the message and respond to it.
     msg.delete(ctx).await?;
-    send!(ctx, msg, HELP_TEXT).await?;
+    send_response(ctx, msg, Response::paginated(HELP_TEXT.to_string())).await?;
 
     Ok(())
 }
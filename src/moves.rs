@@ -0,0 +1,245 @@
+use std::fmt::{Display, Formatter};
+
+use serde::Deserialize;
+
+use crate::rolls::{ActionRoll, Outcome};
+
+/// What matching challenge dice mean for a move, where it differs from the usual.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct MatchText {
+    pub strong_hit: Option<String>,
+    pub weak_hit: Option<String>,
+    pub miss: Option<String>,
+}
+
+/// A move, with what triggers it and what happens on each outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Move {
+    pub name: String,
+    pub category: String,
+    pub trigger: String,
+    pub strong_hit: String,
+    pub weak_hit: String,
+    pub miss: String,
+    #[serde(default, rename = "match")]
+    pub on_match: MatchText,
+}
+
+impl Move {
+    /// What happens on the given outcome.
+    pub fn outcome_text(&self, outcome: Outcome) -> &str {
+        match outcome {
+            Outcome::StrongHit => &self.strong_hit,
+            Outcome::WeakHit => &self.weak_hit,
+            Outcome::Miss => &self.miss,
+        }
+    }
+
+    /// What matching challenge dice mean on the given outcome, if this move says.
+    pub fn match_text(&self, outcome: Outcome) -> Option<&str> {
+        let text = match outcome {
+            Outcome::StrongHit => &self.on_match.strong_hit,
+            Outcome::WeakHit => &self.on_match.weak_hit,
+            Outcome::Miss => &self.on_match.miss,
+        };
+        text.as_deref()
+    }
+
+    /// Is this move called the given name?
+    /// Case is ignored, and hyphens may be used instead of spaces.
+    fn is_called(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(&name.replace('-', " "))
+    }
+
+    /// Does this move mention every one of the given words, ignoring case?
+    fn mentions(&self, words: &[String]) -> bool {
+        let text = format!("{} {}", self.name, self.trigger).to_lowercase();
+        words.iter().all(|word| text.contains(word.as_str()))
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "**{}** ({} Move)", self.name, self.category)?;
+        writeln!(f, "{}", self.trigger)?;
+        for outcome in [Outcome::StrongHit, Outcome::WeakHit, Outcome::Miss] {
+            write!(f, "\n**{}**: {}", outcome, self.outcome_text(outcome))?;
+        }
+        Ok(())
+    }
+}
+
+/// All the moves that can be made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moves {
+    moves: Vec<Move>,
+}
+
+impl Moves {
+    /// Load moves from JSON.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let moves = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Ok(Self { moves })
+    }
+
+    /// Find a move by name.
+    pub fn find(&self, name: &str) -> Option<&Move> {
+        self.moves.iter().find(|m| m.is_called(name))
+    }
+
+    /// Find the move named by the first of the given words, preferring longer names.
+    /// Returns the move and the remaining words.
+    pub fn find_prefix<'a, 'b>(
+        &'a self,
+        words: &'b [&'b str],
+    ) -> Option<(&'a Move, &'b [&'b str])> {
+        (1..=words.len()).rev().find_map(|end| {
+            self.find(&words[..end].join(" "))
+                .map(|found| (found, &words[end..]))
+        })
+    }
+
+    /// Find the moves whose name or trigger mentions all of the given words.
+    pub fn search(&self, words: &[&str]) -> Vec<&Move> {
+        let words = words
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>();
+        self.moves.iter().filter(|m| m.mentions(&words)).collect()
+    }
+}
+
+impl Display for Moves {
+    /// List the names of all the moves, by category.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut categories = Vec::<(&str, Vec<&str>)>::new();
+        for m in &self.moves {
+            match categories.iter_mut().find(|(name, _)| *name == m.category) {
+                Some((_, names)) => names.push(&m.name),
+                None => categories.push((&m.category, vec![&m.name])),
+            }
+        }
+        let lines = categories
+            .iter()
+            .map(|(category, names)| format!("**{}**: {}", category, names.join(", ")))
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// An action roll made for a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveRoll<'a> {
+    pub action: &'a Move,
+    pub roll: ActionRoll,
+}
+
+impl Display for MoveRoll<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.roll.fmt_titled(f, &self.action.name)?;
+        // The outcome is only known if the bonus is.
+        if let Some(outcome) = self.roll.outcome() {
+            write!(
+                f,
+                "\n**{}**: {}",
+                outcome,
+                self.action.outcome_text(outcome)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::Stat;
+    use crate::rolls::Dice;
+    use crate::ruleset::{Ruleset, Starforged};
+
+    #[test]
+    fn finding() {
+        let starforged = Starforged::default();
+        let moves = starforged.moves();
+        assert_eq!(moves.find("face-danger").unwrap().name, "Face Danger");
+        assert_eq!(
+            moves.find("SECURE AN ADVANTAGE").unwrap().name,
+            "Secure an Advantage"
+        );
+        assert_eq!(moves.find("face"), None);
+
+        let (found, rest) = moves
+            .find_prefix(&["gather", "information", "wits", "1"])
+            .unwrap();
+        assert_eq!(found.name, "Gather Information");
+        assert_eq!(rest, ["wits", "1"]);
+        assert_eq!(moves.find_prefix(&["3", "2"]), None);
+    }
+
+    #[test]
+    fn searching() {
+        let starforged = Starforged::default();
+        let moves = starforged.moves();
+        let names = |words: &[&str]| {
+            moves
+                .search(words)
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&["investigation"]), ["Gather Information"]);
+        assert_eq!(names(&["PHYSICAL", "injur"]), ["Endure Harm", "Heal"]);
+        assert!(names(&["dragon"]).is_empty());
+    }
+
+    #[test]
+    fn move_rolls() {
+        let starforged = Starforged::default();
+        let moves = starforged.moves();
+        let face_danger = moves.find("face danger").unwrap();
+        let roll = MoveRoll {
+            action: face_danger,
+            roll: ActionRoll {
+                dice: Dice::default(),
+                action_die: 4,
+                bonus: Some(2),
+                stat: Some(Stat::Edge),
+                momentum: None,
+                challenge_dice: [3, 8],
+            },
+        };
+        assert_eq!(
+            roll.to_string(),
+            format!(
+                "***Face Danger (Edge): [4]+2 = 6 vs [3] [8] (Weak Hit)***\n**Weak Hit**: {}",
+                face_danger.weak_hit
+            )
+        );
+
+        let roll = MoveRoll {
+            roll: ActionRoll {
+                bonus: None,
+                stat: None,
+                ..roll.roll
+            },
+            ..roll
+        };
+        assert_eq!(roll.to_string(), "***Face Danger: [4] vs [3] [8]***");
+    }
+
+    #[test]
+    fn match_text() {
+        let starforged = Starforged::default();
+        let moves = starforged.moves();
+        let waypoint = moves.find("explore a waypoint").unwrap();
+        assert_eq!(
+            waypoint.match_text(Outcome::StrongHit),
+            Some("You may Make a Discovery instead.")
+        );
+        // Outcomes without their own text are left to the ruleset's guidance.
+        assert_eq!(waypoint.match_text(Outcome::WeakHit), None);
+        let face_danger = moves.find("face danger").unwrap();
+        assert_eq!(face_danger.match_text(Outcome::Miss), None);
+    }
+}
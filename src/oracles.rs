@@ -0,0 +1,717 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serenity::prelude::TypeMapKey;
+
+use crate::store::Store;
+use crate::OutputType;
+
+/// How many rolls deep references to other tables are followed.
+const MAX_DEPTH: usize = 4;
+/// The most times a row may ask for a roll on a table.
+const MAX_NUMBER_OF_ROLLS: u8 = 3;
+/// The most further rolls that a single roll on a table may lead to, however deep.
+pub const MAX_FURTHER_ROLLS: usize = 100;
+
+/// The range of rolls that select a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollRange {
+    pub min: OutputType,
+    pub max: OutputType,
+}
+
+/// Further rolls that a row asks for, like "roll twice" or "roll on the Action table".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleRolls {
+    /// The table to roll on, or the same table if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle: Option<String>,
+    #[serde(default = "one")]
+    pub number_of_rolls: u8,
+}
+
+fn one() -> u8 {
+    1
+}
+
+/// A single row of an oracle table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Row {
+    pub roll: RollRange,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub oracle_rolls: Vec<OracleRolls>,
+}
+
+/// A problem with the rows of an oracle table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// The table has no rows at all.
+    Empty,
+    /// A row starts after the end of the previous one.
+    Gap {
+        expected: OutputType,
+        found: OutputType,
+    },
+    /// A row starts before the end of the previous one.
+    Overlap {
+        expected: OutputType,
+        found: OutputType,
+    },
+    /// The rows don't cover exactly the sides of the die.
+    Uncovered {
+        sides: OutputType,
+        covered: OutputType,
+    },
+    /// A row ends before it starts.
+    Backwards(RollRange),
+    /// A row asks for a roll on a table that doesn't exist.
+    UnknownTable(String),
+    /// A row asks for more than `MAX_NUMBER_OF_ROLLS` rolls on a table.
+    TooManyRolls(u8),
+    /// A row ends on the largest roll there can be, leaving no room for a die with more sides.
+    TooLarge(RollRange),
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "it has no rows"),
+            Self::Gap { expected, found } => write!(
+                f,
+                "a row starts at {} when it should start at {}",
+                found, expected
+            ),
+            Self::Overlap { expected, found } => write!(
+                f,
+                "a row starts at {}, overlapping the previous row which ends at {}",
+                found,
+                expected - 1
+            ),
+            Self::Uncovered { sides, covered } => write!(
+                f,
+                "the rows cover 1-{}, but the die has {} sides",
+                covered, sides
+            ),
+            Self::Backwards(range) => {
+                write!(
+                    f,
+                    "the row {}-{} ends before it starts",
+                    range.min, range.max
+                )
+            }
+            Self::UnknownTable(name) => write!(f, "a row refers to the unknown table `{}`", name),
+            Self::TooManyRolls(rolls) => write!(
+                f,
+                "a row asks for {} rolls on a table, but at most {} are allowed",
+                rolls, MAX_NUMBER_OF_ROLLS
+            ),
+            Self::TooLarge(range) => write!(
+                f,
+                "the row {}-{} ends on too large a roll",
+                range.min, range.max
+            ),
+        }
+    }
+}
+
+/// A table of results, chosen by rolling a die.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OracleTable {
+    pub name: String,
+    /// Other names that the table can be found by.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+impl OracleTable {
+    /// The number of sides on the die rolled for this table.
+    pub fn sides(&self) -> OutputType {
+        self.rows.last().map_or(0, |row| row.roll.max)
+    }
+
+    /// Check that the rows cover every roll from 1 upwards exactly once, in order,
+    /// and don't ask for too many further rolls.
+    pub fn validate(&self) -> Result<(), TableError> {
+        if self.rows.is_empty() {
+            return Err(TableError::Empty);
+        }
+        let mut expected = 1;
+        for row in &self.rows {
+            if row.roll.min > expected {
+                return Err(TableError::Gap {
+                    expected,
+                    found: row.roll.min,
+                });
+            }
+            if row.roll.min < expected {
+                return Err(TableError::Overlap {
+                    expected,
+                    found: row.roll.min,
+                });
+            }
+            if row.roll.max < row.roll.min {
+                return Err(TableError::Backwards(row.roll));
+            }
+            expected = row
+                .roll
+                .max
+                .checked_add(1)
+                .ok_or(TableError::TooLarge(row.roll))?;
+            if let Some(rolls) = row
+                .oracle_rolls
+                .iter()
+                .find(|rolls| rolls.number_of_rolls > MAX_NUMBER_OF_ROLLS)
+            {
+                return Err(TableError::TooManyRolls(rolls.number_of_rolls));
+            }
+        }
+        Ok(())
+    }
+
+    /// The row selected by the given roll.
+    pub fn lookup(&self, roll: OutputType) -> Option<&Row> {
+        self.rows
+            .iter()
+            .find(|row| (row.roll.min..=row.roll.max).contains(&roll))
+    }
+
+    /// Is this table known by the given name, ignoring case?
+    fn is_called(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A named group of oracle tables.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct Collection {
+    name: String,
+    oracles: Vec<OracleTable>,
+}
+
+/// All the oracle tables that can be rolled on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Oracles {
+    collections: Vec<Collection>,
+}
+
+impl Oracles {
+    /// Load oracle tables from one or more JSON files, checking that every table is valid.
+    /// Tables may refer to tables in any of the files.
+    pub fn load(files: &[&str]) -> Result<Self, String> {
+        let mut collections = Vec::<Collection>::new();
+        for json in files {
+            collections.extend(serde_json::from_str::<Vec<_>>(json).map_err(|e| e.to_string())?);
+        }
+        let oracles = Self { collections };
+        for table in oracles.tables() {
+            table
+                .validate()
+                .and_then(|()| oracles.check_references(table))
+                .map_err(|e| format!("Invalid table `{}`: {}", table.name, e))?;
+        }
+        Ok(oracles)
+    }
+
+    /// Add a guild's custom tables, which are found before any others.
+    pub fn with_custom(&self, tables: Vec<OracleTable>) -> Self {
+        if tables.is_empty() {
+            return self.clone();
+        }
+        let custom = Collection {
+            name: "Custom".to_string(),
+            oracles: tables,
+        };
+        let collections = std::iter::once(custom)
+            .chain(self.collections.iter().cloned())
+            .collect();
+        Self { collections }
+    }
+
+    /// Check that every table a row refers to exists.
+    pub fn check_references(&self, table: &OracleTable) -> Result<(), TableError> {
+        let names = table
+            .rows
+            .iter()
+            .flat_map(|row| &row.oracle_rolls)
+            .filter_map(|rolls| rolls.oracle.as_ref());
+        for name in names {
+            if self.find(name).is_none() {
+                return Err(TableError::UnknownTable(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// All the tables, in order.
+    fn tables(&self) -> impl Iterator<Item = &OracleTable> {
+        self.collections.iter().flat_map(|c| &c.oracles)
+    }
+
+    /// Find a table by its name or one of its aliases, ignoring case.
+    pub fn find(&self, name: &str) -> Option<&OracleTable> {
+        self.tables().find(|table| table.is_called(name))
+    }
+
+    /// Split some words into the names of tables, preferring longer names where they overlap.
+    /// If some words don't name a table, the first of them is returned as the error.
+    pub fn resolve<'a>(&self, words: &[&'a str]) -> Result<Vec<&OracleTable>, &'a str> {
+        let mut tables = Vec::new();
+        let mut start = 0;
+        while start < words.len() {
+            let (end, table) = (start + 1..=words.len())
+                .rev()
+                .find_map(|end| {
+                    self.find(&words[start..end].join(" "))
+                        .map(|table| (end, table))
+                })
+                .ok_or(words[start])?;
+            tables.push(table);
+            start = end;
+        }
+        Ok(tables)
+    }
+}
+
+impl Display for Oracles {
+    /// List the names of all the tables, by collection.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let lines = self
+            .collections
+            .iter()
+            .map(|collection| {
+                let names = collection
+                    .oracles
+                    .iter()
+                    .map(|table| table.name.as_str())
+                    .collect::<Vec<_>>();
+                format!("**{}**: {}", collection.name, names.join(", "))
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// The key for the store of custom tables that each guild has imported.
+pub struct CustomOracles;
+
+impl TypeMapKey for CustomOracles {
+    type Value = Arc<Store<Vec<OracleTable>>>;
+}
+
+/// Why a row's further rolls weren't all made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stopped {
+    /// Following the reference would roll on a table that is already being resolved.
+    Loop,
+    /// The references went deeper than `MAX_DEPTH`.
+    TooDeep,
+    /// The roll already led to `MAX_FURTHER_ROLLS` further rolls.
+    TooMany,
+}
+
+impl Display for Stopped {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Loop => write!(f, "not rolled again to avoid a loop"),
+            Self::TooDeep => write!(f, "too deep to roll further"),
+            Self::TooMany => write!(f, "too many rolls to roll further"),
+        }
+    }
+}
+
+/// A roll on an oracle table, with the row it selected and any further rolls that row asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRoll {
+    pub table: String,
+    pub roll: OutputType,
+    pub result: String,
+    pub children: Vec<TableRoll>,
+    pub stopped: Option<Stopped>,
+}
+
+impl TableRoll {
+    /// Roll on the given table using the given source of randomness,
+    /// following any references to other tables.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, oracles: &Oracles, table: &OracleTable) -> Self {
+        let mut rolls_left = MAX_FURTHER_ROLLS;
+        Self::resolve(rng, oracles, table, &mut Vec::new(), &mut rolls_left)
+    }
+
+    /// Roll on a table, given the names of the tables above it that are still being resolved
+    /// and how many further rolls may still be made.
+    fn resolve<R: Rng + ?Sized>(
+        rng: &mut R,
+        oracles: &Oracles,
+        table: &OracleTable,
+        ancestors: &mut Vec<String>,
+        rolls_left: &mut usize,
+    ) -> Self {
+        let roll = rng.gen_range(1..=table.sides());
+        let row = table.lookup(roll);
+        let mut result = Self {
+            table: table.name.clone(),
+            roll,
+            result: row.map_or_else(String::new, |row| row.text.clone()),
+            children: Vec::new(),
+            stopped: None,
+        };
+        let Some(row) = row.filter(|row| !row.oracle_rolls.is_empty()) else {
+            return result;
+        };
+        if ancestors.len() >= MAX_DEPTH {
+            result.stopped = Some(Stopped::TooDeep);
+            return result;
+        }
+
+        ancestors.push(table.name.clone());
+        for rolls in &row.oracle_rolls {
+            let target = match &rolls.oracle {
+                Some(name) => oracles.find(name),
+                None => Some(table),
+            };
+            // References were checked when the tables were loaded.
+            let Some(target) = target else {
+                continue;
+            };
+            // Rolling twice on the same table is fine, but coming back to it from another isn't.
+            if target.name != table.name && ancestors.contains(&target.name) {
+                result.stopped = Some(Stopped::Loop);
+                continue;
+            }
+            for _ in 0..rolls.number_of_rolls {
+                if *rolls_left == 0 {
+                    result.stopped = Some(Stopped::TooMany);
+                    break;
+                }
+                *rolls_left -= 1;
+                let child = Self::resolve(rng, oracles, target, ancestors, rolls_left);
+                result.children.push(child);
+            }
+        }
+        ancestors.pop();
+        result
+    }
+
+    /// Write this roll and its further rolls as a tree, indented by depth.
+    fn write_tree(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        if depth > 0 {
+            write!(f, "\n{}↳ ", "\u{2003}".repeat(depth - 1))?;
+        }
+        write!(f, "**{}** [{}]: {}", self.table, self.roll, self.result)?;
+        if let Some(stopped) = self.stopped {
+            write!(f, " ({})", stopped)?;
+        }
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TableRoll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+/// Rolls on several oracle tables at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRolls {
+    pub rolls: Vec<TableRoll>,
+}
+
+impl TableRolls {
+    /// Roll once on each of the given tables using the given source of randomness.
+    pub fn random<R: Rng + ?Sized>(
+        rng: &mut R,
+        oracles: &Oracles,
+        tables: &[&OracleTable],
+    ) -> Self {
+        let rolls = tables
+            .iter()
+            .map(|table| TableRoll::random(rng, oracles, table))
+            .collect();
+        Self { rolls }
+    }
+}
+
+impl Display for TableRolls {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "***Oracle Roll***")?;
+        for roll in &self.rolls {
+            write!(f, "\n{}", roll)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::ruleset::{Ruleset, Starforged};
+
+    fn table(rows: &[(OutputType, OutputType, &str)]) -> OracleTable {
+        OracleTable {
+            name: "Test".to_string(),
+            aliases: Vec::new(),
+            rows: rows
+                .iter()
+                .map(|&(min, max, text)| Row {
+                    roll: RollRange { min, max },
+                    text: text.to_string(),
+                    oracle_rolls: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn leaf(table: &str, roll: OutputType, result: &str) -> TableRoll {
+        TableRoll {
+            table: table.to_string(),
+            roll,
+            result: result.to_string(),
+            children: Vec::new(),
+            stopped: None,
+        }
+    }
+
+    #[test]
+    fn bundled_tables_are_valid() {
+        let starforged = Starforged::default();
+        let oracles = starforged.oracles();
+        for name in [
+            "Action",
+            "Theme",
+            "Pay the Price",
+            "Planet Type",
+            "Stellar Object",
+        ] {
+            let table = oracles.find(name).unwrap();
+            assert_eq!(table.sides(), 100, "{}", name);
+        }
+        assert_eq!(
+            oracles.find("action").unwrap().lookup(1).unwrap().text,
+            "Abandon"
+        );
+        assert_eq!(
+            oracles
+                .find("planetary class")
+                .unwrap()
+                .lookup(36)
+                .unwrap()
+                .text,
+            "Ice World"
+        );
+    }
+
+    #[test]
+    fn resolving() {
+        let starforged = Starforged::default();
+        let oracles = starforged.oracles();
+        let names = |words: &[&'static str]| {
+            oracles
+                .resolve(words)
+                .map(|tables| tables.iter().map(|t| t.name.clone()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            names(&["action", "theme"]),
+            Ok(vec!["Action".into(), "Theme".into()])
+        );
+        assert_eq!(names(&["planet", "type"]), Ok(vec!["Planet Type".into()]));
+        assert_eq!(
+            names(&["Planetary", "Class", "star"]),
+            Ok(vec!["Planet Type".into(), "Stellar Object".into()])
+        );
+        assert_eq!(names(&["action", "planet"]), Err("planet"));
+        assert_eq!(names(&["dragon"]), Err("dragon"));
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!(table(&[(1, 50, "a"), (51, 100, "b")]).validate(), Ok(()));
+        assert_eq!(table(&[]).validate(), Err(TableError::Empty));
+        assert_eq!(
+            table(&[(1, 50, "a"), (52, 100, "b")]).validate(),
+            Err(TableError::Gap {
+                expected: 51,
+                found: 52
+            })
+        );
+        assert_eq!(
+            table(&[(2, 100, "a")]).validate(),
+            Err(TableError::Gap {
+                expected: 1,
+                found: 2
+            })
+        );
+        assert_eq!(
+            table(&[(1, 50, "a"), (50, 100, "b")]).validate(),
+            Err(TableError::Overlap {
+                expected: 51,
+                found: 50
+            })
+        );
+        assert_eq!(
+            table(&[(1, 60, "a"), (61, 50, "b")]).validate(),
+            Err(TableError::Backwards(RollRange { min: 61, max: 50 }))
+        );
+        let error =
+            Oracles::load(&[r#"[{"name": "Bad", "oracles": [{"name": "Broken", "rows": []}]}]"#])
+                .unwrap_err();
+        assert_eq!(error, "Invalid table `Broken`: it has no rows");
+    }
+
+    #[test]
+    fn rolling() {
+        let table = table(&[(1, 3, "low"), (4, 6, "high")]);
+        let oracles = Oracles::load(&["[]"]).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let roll = TableRoll::random(&mut rng, &oracles, &table);
+            assert!((1..=6).contains(&roll.roll));
+            assert_eq!(roll.result, if roll.roll <= 3 { "low" } else { "high" });
+        }
+
+        let rolls = TableRolls {
+            rolls: vec![
+                leaf("Action", 58, "Investigate"),
+                leaf("Theme", 7, "Belief"),
+            ],
+        };
+        assert_eq!(
+            rolls.to_string(),
+            "***Oracle Roll***\n**Action** [58]: Investigate\n**Theme** [7]: Belief"
+        );
+    }
+
+    #[test]
+    fn rolling_twice() {
+        // Every roll asks for two more, so only the depth limit stops it.
+        let oracles =
+            Oracles::load(
+                &[r#"[{"name": "Test", "oracles": [{"name": "Twice", "rows": [
+                {"roll": {"min": 1, "max": 1}, "text": "Roll twice",
+                 "oracle_rolls": [{"number_of_rolls": 2}]}
+            ]}]}]"#],
+            )
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        let roll = TableRoll::random(&mut rng, &oracles, oracles.find("twice").unwrap());
+
+        let mut level = vec![&roll];
+        for _ in 0..MAX_DEPTH {
+            assert!(level.iter().all(|roll| roll.stopped.is_none()));
+            level = level.iter().flat_map(|roll| &roll.children).collect();
+        }
+        assert_eq!(level.len(), 1 << MAX_DEPTH);
+        assert!(level
+            .iter()
+            .all(|roll| roll.children.is_empty() && roll.stopped == Some(Stopped::TooDeep)));
+    }
+
+    #[test]
+    fn too_many_rolls() {
+        // Three rolls at every level would make 3 + 9 + 27 + 81 further rolls.
+        let oracles = Oracles::load(&[
+            r#"[{"name": "Test", "oracles": [{"name": "Thrice", "rows": [
+                {"roll": {"min": 1, "max": 1}, "text": "Roll three times",
+                 "oracle_rolls": [{"number_of_rolls": 3}]}
+            ]}]}]"#,
+        ])
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        let roll = TableRoll::random(&mut rng, &oracles, oracles.find("thrice").unwrap());
+
+        fn count(roll: &TableRoll) -> usize {
+            roll.children.iter().map(|child| 1 + count(child)).sum()
+        }
+        fn stopped(roll: &TableRoll) -> bool {
+            roll.stopped == Some(Stopped::TooMany) || roll.children.iter().any(stopped)
+        }
+        assert_eq!(count(&roll), MAX_FURTHER_ROLLS);
+        assert!(stopped(&roll));
+
+        let error = Oracles::load(&[
+            r#"[{"name": "Test", "oracles": [{"name": "Forever", "rows": [
+                {"roll": {"min": 1, "max": 1}, "text": "Roll again",
+                 "oracle_rolls": [{"number_of_rolls": 255}]}
+            ]}]}]"#,
+        ])
+        .unwrap_err();
+        assert_eq!(
+            error,
+            "Invalid table `Forever`: a row asks for 255 rolls on a table, but at most 3 are allowed"
+        );
+    }
+
+    #[test]
+    fn references() {
+        let oracles = Oracles::load(&[r#"[{"name": "Test", "oracles": [
+                {"name": "Ping", "rows": [{"roll": {"min": 1, "max": 1}, "text": "Roll on Pong",
+                                           "oracle_rolls": [{"oracle": "pong"}]}]},
+                {"name": "Pong", "rows": [{"roll": {"min": 1, "max": 1}, "text": "Roll on Ping",
+                                           "oracle_rolls": [{"oracle": "Ping"}]}]}
+            ]}]"#])
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        let roll = TableRoll::random(&mut rng, &oracles, oracles.find("ping").unwrap());
+        assert_eq!(
+            roll,
+            TableRoll {
+                children: vec![TableRoll {
+                    stopped: Some(Stopped::Loop),
+                    ..leaf("Pong", 1, "Roll on Ping")
+                }],
+                ..leaf("Ping", 1, "Roll on Pong")
+            }
+        );
+        assert_eq!(
+            roll.to_string(),
+            "**Ping** [1]: Roll on Pong\n\
+↳ **Pong** [1]: Roll on Ping (not rolled again to avoid a loop)"
+        );
+
+        let error = Oracles::load(
+            &[r#"[{"name": "Test", "oracles": [{"name": "Lost", "rows": [
+                {"roll": {"min": 1, "max": 1}, "text": "?", "oracle_rolls": [{"oracle": "Nowhere"}]}
+            ]}]}]"#],
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            "Invalid table `Lost`: a row refers to the unknown table `Nowhere`"
+        );
+    }
+
+    #[test]
+    fn tree_display() {
+        let roll = TableRoll {
+            children: vec![
+                TableRoll {
+                    children: vec![leaf("Action", 3, "Advance"), leaf("Theme", 7, "Belief")],
+                    ..leaf("Pay the Price", 99, "Roll on the Action and Theme tables")
+                },
+                leaf("Pay the Price", 60, "It is harmful"),
+            ],
+            ..leaf("Pay the Price", 1, "Roll twice")
+        };
+        assert_eq!(
+            roll.to_string(),
+            "**Pay the Price** [1]: Roll twice\n\
+↳ **Pay the Price** [99]: Roll on the Action and Theme tables\n\
+\u{2003}↳ **Action** [3]: Advance\n\
+\u{2003}↳ **Theme** [7]: Belief\n\
+↳ **Pay the Price** [60]: It is harmful"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+/// The maximum length of a Discord message, in characters.
+const MESSAGE_LIMIT: usize = 2000;
+
+/// The formatting that our rolls wrap their whole message in.
+const EMPHASIS: &str = "***";
+
+/// A response to a command, arranged to fit within Discord's message length limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// One or more messages, to be sent in order.
+    Messages(Vec<String>),
+    /// A short message, with the full response attached as a file.
+    Attachment {
+        message: String,
+        filename: String,
+        contents: String,
+    },
+}
+
+/// Does this text fit in a single message?
+fn fits(text: &str) -> bool {
+    text.chars().count() <= MESSAGE_LIMIT
+}
+
+impl Response {
+    /// Send the response as a single message if it fits, otherwise split it across several.
+    pub fn paginated(full: String) -> Self {
+        if fits(&full) {
+            return Self::Messages(vec![full]);
+        }
+        // Formatting that wraps the whole response is reapplied to each part.
+        let parts = match full
+            .strip_prefix(EMPHASIS)
+            .and_then(|inner| inner.strip_suffix(EMPHASIS))
+        {
+            Some(inner) => split_text(inner, MESSAGE_LIMIT - 2 * EMPHASIS.len())
+                .into_iter()
+                .map(|part| format!("{}{}{}", EMPHASIS, part, EMPHASIS))
+                .collect(),
+            None => split_text(&full, MESSAGE_LIMIT),
+        };
+        Self::Messages(parts)
+    }
+
+    /// Send the response as a single message if it fits, otherwise send the summary
+    /// with the full response attached as a file.
+    pub fn summarised(full: String, summary: String, filename: &str) -> Self {
+        if fits(&full) {
+            return Self::Messages(vec![full]);
+        }
+        let message = if fits(&summary) {
+            summary
+        } else {
+            "The result is too long to show here, so it is attached instead.".to_string()
+        };
+        Self::Attachment {
+            message,
+            filename: filename.to_string(),
+            contents: full,
+        }
+    }
+}
+
+/// Split some text into parts of at most `limit` characters.
+/// Parts are broken at paragraphs, lines, or words where possible.
+fn split_text(text: &str, limit: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while rest.chars().count() > limit {
+        // The byte index just after the last character that fits.
+        let end = rest
+            .char_indices()
+            .nth(limit)
+            .map_or(rest.len(), |(i, _)| i);
+        let head = &rest[..end];
+        let split = ["\n\n", "\n", " "]
+            .iter()
+            .find_map(|separator| head.rfind(separator).filter(|&i| i > 0))
+            .unwrap_or(end);
+        parts.push(rest[..split].trim_end().to_string());
+        rest = rest[split..].trim_start();
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_responses_unchanged() {
+        let text = "***Oracle Roll: [42]***".to_string();
+        assert_eq!(
+            Response::paginated(text.clone()),
+            Response::Messages(vec![text.clone()])
+        );
+        assert_eq!(
+            Response::summarised(text.clone(), "summary".to_string(), "roll.txt"),
+            Response::Messages(vec![text])
+        );
+    }
+
+    #[test]
+    fn splitting() {
+        let words = vec!["[100]"; 1000].join(" ");
+        let Response::Messages(parts) = Response::paginated(format!("***{}***", words)) else {
+            panic!("Expected messages");
+        };
+        assert_eq!(parts.len(), 4);
+        for part in &parts {
+            assert!(fits(part));
+            assert!(part.starts_with("***[100]") && part.ends_with("[100]***"));
+        }
+        // No words are lost or broken.
+        let rejoined = parts
+            .iter()
+            .map(|part| part.trim_matches('*'))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(rejoined, words);
+    }
+
+    #[test]
+    fn splitting_prefers_paragraphs() {
+        let paragraph = "word ".repeat(300);
+        let text = format!("{}\n\n{}", paragraph.trim(), paragraph.trim());
+        assert_eq!(
+            split_text(&text, MESSAGE_LIMIT),
+            vec![paragraph.trim(), paragraph.trim()]
+        );
+        // Text without any breaks is cut wherever it must be.
+        let unbroken = "x".repeat(4500);
+        let parts = split_text(&unbroken, MESSAGE_LIMIT);
+        assert_eq!(
+            parts.iter().map(String::len).collect::<Vec<_>>(),
+            [2000, 2000, 500]
+        );
+    }
+
+    #[test]
+    fn summarising() {
+        let full = "[6] ".repeat(1000);
+        assert_eq!(
+            Response::summarised(full.clone(), "Total: 6000".to_string(), "roll.txt"),
+            Response::Attachment {
+                message: "Total: 6000".to_string(),
+                filename: "roll.txt".to_string(),
+                contents: full.clone(),
+            }
+        );
+        let Response::Attachment { message, .. } =
+            Response::summarised(full.clone(), full, "roll.txt")
+        else {
+            panic!("Expected an attachment");
+        };
+        assert!(fits(&message));
+    }
+}
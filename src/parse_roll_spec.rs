@@ -1,9 +1,35 @@
-use logos::{Lexer, Logos};
+use std::iter::Peekable;
+use std::str::FromStr;
 
-use crate::{rolls::RollSpec, InputType};
+use logos::{Logos, Span, SpannedIter};
+
+use crate::{
+    expr::{Expr, Function, Operator},
+    rolls::{DiceGroup, KeepRule, Reroll, RollSpec, SuccessRule, Target},
+    InputType, OutputType,
+};
+
+/// How deeply parentheses, function calls, and negations may be nested.
+const MAX_NESTING: usize = 64;
+
+/// Why the lexer rejected some input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+enum LexError {
+    /// The input isn't part of our syntax.
+    #[default]
+    Unrecognised,
+    /// The input contains a number that's too large.
+    TooLarge,
+}
+
+/// Parse a number from a slice of digits.
+fn parse_number<T: FromStr>(slice: &str) -> Result<T, LexError> {
+    // The only way a string of digits can fail to parse is by being too large.
+    slice.parse().map_err(|_| LexError::TooLarge)
+}
 
 /// Parse the numbers from a slice of `XdY` format.
-fn parse_xdy(slice: &str) -> Option<(InputType, InputType)> {
+fn parse_xdy(slice: &str) -> Result<(OutputType, OutputType), LexError> {
     // Either 'd' or 'D' is guaranteed by the format.
     let (count, size) = slice
         .split_once('d')
@@ -12,108 +38,557 @@ fn parse_xdy(slice: &str) -> Option<(InputType, InputType)> {
     let count = if count.is_empty() {
         1
     } else {
-        count.parse().ok()?
+        parse_number(count)?
     };
     // The size must be present.
-    let size = size.parse().ok()?;
-    Some((count, size))
+    let size = parse_number(size)?;
+    Ok((count, size))
+}
+
+/// Parse a keep or drop modifier of `khX`, `klX`, `dhX`, or `dlX` format.
+fn parse_keep(slice: &str) -> Result<KeepRule, LexError> {
+    // The first two characters are guaranteed ASCII by the format.
+    let (rule, count) = slice.split_at(2);
+    // The count defaults to 1 if missing.
+    let count = if count.is_empty() {
+        1
+    } else {
+        parse_number(count)?
+    };
+    let rule = match rule.to_ascii_lowercase().as_str() {
+        "kh" => KeepRule::KeepHighest(count),
+        "kl" => KeepRule::KeepLowest(count),
+        "dh" => KeepRule::DropHighest(count),
+        "dl" => KeepRule::DropLowest(count),
+        _ => unreachable!(),
+    };
+    Ok(rule)
+}
+
+/// Parse an explosion modifier of `!` or `!>X` format.
+/// The threshold is `None` if the dice explode on their maximum.
+fn parse_explode(slice: &str) -> Result<Option<OutputType>, LexError> {
+    match slice.strip_prefix("!>") {
+        Some(threshold) => Ok(Some(parse_number(threshold)?)),
+        None => Ok(None),
+    }
+}
+
+/// Parse a reroll modifier of `rX`, `r<X`, `roX`, or `ro<X` format.
+fn parse_reroll(slice: &str) -> Result<Reroll, LexError> {
+    // The first character is guaranteed to be 'r' or 'R' by the format.
+    let slice = &slice[1..];
+    let (once, slice) = match slice.strip_prefix(['o', 'O']) {
+        Some(slice) => (true, slice),
+        None => (false, slice),
+    };
+    let threshold = parse_number(slice.trim_start_matches('<'))?;
+    Ok(Reroll { threshold, once })
+}
+
+/// Parse a success target of `>X`, `>=X`, `<X`, or `<=X` format.
+fn parse_target(slice: &str) -> Result<Target, LexError> {
+    // The first character is guaranteed to be '>' or '<' by the format.
+    let (comparison, threshold) = slice.split_at(1);
+    let threshold = parse_number(threshold.trim_start_matches('='))?;
+    let target = match comparison {
+        ">" => Target::AtLeast(threshold),
+        "<" => Target::AtMost(threshold),
+        _ => unreachable!(),
+    };
+    Ok(target)
+}
+
+/// Parse a failure threshold of `fX`, `f<X`, or `f<=X` format.
+fn parse_failure(slice: &str) -> Result<OutputType, LexError> {
+    // The first character is guaranteed to be 'f' or 'F' by the format.
+    parse_number(slice[1..].trim_start_matches(['<', '=']))
 }
 
 /// A token that we use to parse.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Logos)]
-#[logos(skip r"\s+")]
+#[logos(skip r"\s+", error = LexError)]
 enum Token {
     /// An `XdY` roll specification.
     #[regex(r"\d*(d|D)\d+", |lex| parse_xdy(lex.slice()))]
-    XdY((InputType, InputType)),
+    XdY((OutputType, OutputType)),
+
+    /// A keep or drop modifier, e.g. `kh3`.
+    #[regex(r"(k|K|d|D)(h|H|l|L)\d*", |lex| parse_keep(lex.slice()))]
+    Keep(KeepRule),
+
+    /// An explosion modifier, e.g. `!` or `!>5`.
+    #[regex(r"!(>\d+)?", |lex| parse_explode(lex.slice()))]
+    Explode(Option<OutputType>),
+
+    /// A reroll modifier, e.g. `r1` or `ro<2`.
+    #[regex(r"(r|R)(o|O)?<?\d+", |lex| parse_reroll(lex.slice()))]
+    Reroll(Reroll),
+
+    /// A dice pool success target, e.g. `>=7`.
+    #[regex(r"(>|<)=?\d+", |lex| parse_target(lex.slice()))]
+    Target(Target),
 
-    /// A bonus specification.
-    #[regex(r"\d+", |lex| lex.slice().parse().ok())]
-    Bonus(InputType),
+    /// A dice pool failure threshold, e.g. `f1`.
+    #[regex(r"(f|F)(<=?)?\d+", |lex| parse_failure(lex.slice()))]
+    Failure(OutputType),
+
+    /// A plain number.
+    #[regex(r"\d+", |lex| parse_number(lex.slice()))]
+    Number(InputType),
+
+    /// The name of a function, e.g. `max`.
+    #[regex(r"(?i)max|min|floor|ceil|round|abs", |lex| Function::from_name(lex.slice()))]
+    Function(Function),
 
     /// The `+` character.
     #[token("+")]
     Plus,
+
+    /// The `-` character.
+    #[token("-")]
+    Minus,
+
+    /// The `*` character.
+    #[token("*")]
+    Star,
+
+    /// The `/` character.
+    #[token("/")]
+    Slash,
+
+    /// The `(` character.
+    #[token("(")]
+    OpenParen,
+
+    /// The `)` character.
+    #[token(")")]
+    CloseParen,
+
+    /// The `,` character.
+    #[token(",")]
+    Comma,
 }
 
-/// Parse a `RollSpec` from a string slice.
-pub fn parse(input: &str) -> Result<RollSpec, ()> {
-    let mut lex: Lexer<Token> = Token::lexer(input);
-    let mut dice = Vec::new();
-    let mut bonuses = Vec::new();
+/// The different ways parsing can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Something that isn't part of the syntax at all, e.g. `fish`.
+    Unrecognised,
+    /// Something valid in the wrong place, e.g. the second `+` in `2d6 ++ 1`.
+    UnexpectedToken,
+    /// A number that's too large.
+    NumberTooLarge,
+    /// An operator with nothing after it, e.g. `2d6 +`.
+    DanglingOperator,
+    /// A parenthesis that is never closed.
+    UnclosedParen,
+    /// A function name without its arguments, e.g. `max`.
+    MissingArguments,
+    /// A function given the wrong number of arguments, e.g. `floor(d6, 2)`.
+    WrongArgumentCount,
+    /// A modifier that doesn't make sense for its dice, with the reason why.
+    InvalidModifier(&'static str),
+    /// A specification without any dice.
+    MissingDie,
+    /// Dice without any sides, e.g. `2d0`.
+    NoSides,
+    /// Something nested more than `MAX_NESTING` deep, e.g. `((((d6))))` but far longer.
+    TooDeep,
+}
 
-    while let Some(token) = lex.next() {
-        // Get the next die or bonus.
-        match token {
-            Ok(Token::XdY((count, size))) => {
-                for _ in 0..count {
-                    dice.push(size);
-                }
+/// Why a roll specification couldn't be parsed, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The byte range of the input at fault.
+    pub span: Span,
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Describe this error in full, quoting the input with the faulty part underlined.
+    pub fn describe(&self, input: &str) -> String {
+        let slice = &input[self.span.clone()];
+        let message = match self.kind {
+            ParseErrorKind::Unrecognised => format!("`{}` is not recognised", slice),
+            ParseErrorKind::UnexpectedToken => format!("`{}` was unexpected", slice),
+            ParseErrorKind::NumberTooLarge => format!("`{}` is too large", slice),
+            ParseErrorKind::DanglingOperator => format!("`{}` needs something after it", slice),
+            ParseErrorKind::UnclosedParen => format!("`{}` is never closed", slice),
+            ParseErrorKind::MissingArguments => {
+                format!("`{}` needs arguments, e.g. `{}(d6)`", slice, slice)
             }
-            Ok(Token::Bonus(bonus)) => {
-                bonuses.push(bonus);
+            ParseErrorKind::WrongArgumentCount => {
+                format!("`{}` has the wrong number of arguments", slice)
             }
-            Ok(Token::Plus) => {
-                return Err(());
+            ParseErrorKind::InvalidModifier(reason) => format!("`{}` {}", slice, reason),
+            ParseErrorKind::MissingDie => "there must be at least one die".to_string(),
+            ParseErrorKind::NoSides => format!("`{}` must have at least one side", slice),
+            ParseErrorKind::TooDeep => format!("`{}` is nested too deeply", slice),
+        };
+
+        // Point out the problem, counting characters rather than bytes.
+        let padding = input[..self.span.start].chars().count();
+        let width = slice.chars().count().max(1);
+        format!(
+            "{}\n```\n{}\n{}{}\n```",
+            message,
+            input,
+            " ".repeat(padding),
+            "^".repeat(width)
+        )
+    }
+}
+
+/// A recursive-descent parser over a stream of tokens.
+struct Parser<'a> {
+    tokens: Peekable<SpannedIter<'a, Token>>,
+    /// The span of the most recently consumed token, if any.
+    last: Option<Span>,
+    /// How deeply nested the term being parsed is.
+    depth: usize,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            tokens: Token::lexer(input).spanned().peekable(),
+            last: None,
+            depth: 0,
+        }
+    }
+
+    /// Look at the next token without consuming it.
+    fn peek(&mut self) -> Option<(Result<Token, LexError>, Span)> {
+        self.tokens.peek().cloned()
+    }
+
+    /// Consume the next token, failing if it couldn't be lexed or there are none left.
+    fn next(&mut self) -> ParseResult<(Token, Span)> {
+        match self.tokens.next() {
+            Some((Ok(token), span)) => {
+                self.last = Some(span.clone());
+                Ok((token, span))
             }
-            Err(_) => {
-                return Err(());
+            Some((Err(e), span)) => Err(lex_error(e, span)),
+            None => Err(self.unexpected_end()),
+        }
+    }
+
+    /// Consume the next token if it is the one expected.
+    fn eat(&mut self, expected: Token) -> bool {
+        let found = matches!(self.tokens.peek(), Some((Ok(token), _)) if *token == expected);
+        if found {
+            self.last = self.tokens.next().map(|(_, span)| span);
+        }
+        found
+    }
+
+    /// The error for when the input runs out early.
+    fn unexpected_end(&self) -> ParseError {
+        match &self.last {
+            Some(span) => ParseError::new(ParseErrorKind::DanglingOperator, span.clone()),
+            None => ParseError::new(ParseErrorKind::MissingDie, 0..0),
+        }
+    }
+
+    /// The error for the next token being in the wrong place.
+    fn unexpected(&mut self) -> ParseError {
+        match self.tokens.next() {
+            Some((Ok(_), span)) => ParseError::new(ParseErrorKind::UnexpectedToken, span),
+            Some((Err(e), span)) => lex_error(e, span),
+            None => self.unexpected_end(),
+        }
+    }
+
+    /// Parse something nested within the token at `span`, failing if it's too deep.
+    /// This keeps the recursion from overflowing the stack.
+    fn nested<T>(
+        &mut self,
+        span: Span,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        if self.depth >= MAX_NESTING {
+            return Err(ParseError::new(ParseErrorKind::TooDeep, span));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Parse a sum or difference of products, e.g. `2d6 + 3 - d4`.
+    fn sum(&mut self) -> ParseResult<RollSpec> {
+        let mut expr = self.product()?;
+        loop {
+            let operator = match self.peek() {
+                Some((Ok(Token::Plus), _)) => Operator::Add,
+                Some((Ok(Token::Minus), _)) => Operator::Subtract,
+                _ => return Ok(expr),
+            };
+            self.next()?;
+            expr = Expr::binary(operator, expr, self.product()?);
+        }
+    }
+
+    /// Parse a product or quotient of terms, e.g. `2d6 * 2 / 3`.
+    fn product(&mut self) -> ParseResult<RollSpec> {
+        let mut expr = self.unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some((Ok(Token::Star), _)) => Operator::Multiply,
+                Some((Ok(Token::Slash), _)) => Operator::Divide,
+                _ => return Ok(expr),
+            };
+            self.next()?;
+            expr = Expr::binary(operator, expr, self.unary()?);
+        }
+    }
+
+    /// Parse a possibly-negated term, e.g. `-2`.
+    fn unary(&mut self) -> ParseResult<RollSpec> {
+        if let Some((Ok(Token::Minus), span)) = self.peek() {
+            self.next()?;
+            Ok(Expr::Negate(Box::new(self.nested(span, Self::unary)?)))
+        } else {
+            self.atom()
+        }
+    }
+
+    /// Parse a single term: a number, some dice, a parenthesised expression,
+    /// or a function call.
+    fn atom(&mut self) -> ParseResult<RollSpec> {
+        let (token, span) = self.next()?;
+        match token {
+            Token::Number(n) => Ok(Expr::Number(n)),
+            Token::XdY((count, size)) => Ok(Expr::Dice(self.dice(count, size, span)?)),
+            Token::OpenParen => {
+                let expr = self.nested(span.clone(), Self::sum)?;
+                self.close_paren(span)?;
+                Ok(expr)
             }
+            Token::Function(function) => {
+                let open = match self.peek() {
+                    Some((Ok(Token::OpenParen), open)) => open,
+                    _ => return Err(ParseError::new(ParseErrorKind::MissingArguments, span)),
+                };
+                self.next()?;
+                let args = self.nested(span.clone(), |parser| {
+                    let mut args = vec![parser.sum()?];
+                    while parser.eat(Token::Comma) {
+                        args.push(parser.sum()?);
+                    }
+                    Ok(args)
+                })?;
+                let close = self.close_paren(open)?;
+                if !function.accepts(args.len()) {
+                    let kind = ParseErrorKind::WrongArgumentCount;
+                    return Err(ParseError::new(kind, span.start..close.end));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            // Modifiers and operators can't start a term.
+            _ => Err(ParseError::new(ParseErrorKind::UnexpectedToken, span)),
         }
-        // If there are more tokens, we must see a plus before anything else.
-        if let Some(token) = lex.next() {
+    }
+
+    /// Consume the parenthesis closing the one at `open`, returning its span.
+    fn close_paren(&mut self, open: Span) -> ParseResult<Span> {
+        match self.peek() {
+            Some((Ok(Token::CloseParen), close)) => {
+                self.next()?;
+                Ok(close)
+            }
+            None => Err(ParseError::new(ParseErrorKind::UnclosedParen, open)),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    /// Parse a group of dice, along with any modifiers immediately following it.
+    fn dice(&mut self, count: OutputType, size: OutputType, span: Span) -> ParseResult<DiceGroup> {
+        // A die without sides can't be rolled.
+        if size == 0 {
+            return Err(ParseError::new(ParseErrorKind::NoSides, span));
+        }
+        let invalid = |reason, span| ParseError::new(ParseErrorKind::InvalidModifier(reason), span);
+        let mut group = DiceGroup::new(count, size);
+        let mut end = span.end;
+        // Modifiers must directly follow the dice, without whitespace.
+        // Each may only be given once.
+        while let Some((Ok(token), span)) = self.peek() {
+            if span.start != end {
+                break;
+            }
             match token {
-                Ok(Token::Plus) => {} // ok
-                _ => return Err(()),
+                Token::Keep(keep) if group.keep.is_none() => {
+                    // We can't keep or drop more dice than were rolled.
+                    let (KeepRule::KeepHighest(n)
+                    | KeepRule::KeepLowest(n)
+                    | KeepRule::DropHighest(n)
+                    | KeepRule::DropLowest(n)) = keep;
+                    if n > count {
+                        return Err(invalid("keeps or drops more dice than are rolled", span));
+                    }
+                    group.keep = Some(keep);
+                }
+                Token::Explode(explode) if group.explode.is_none() => {
+                    // Every value exploding would never end.
+                    let explode = explode.unwrap_or(size);
+                    if !(2..=size).contains(&explode) {
+                        return Err(invalid(
+                            "must explode on at least 2 and at most the die size",
+                            span,
+                        ));
+                    }
+                    group.explode = Some(explode);
+                }
+                Token::Reroll(reroll) if group.reroll.is_none() => {
+                    // Every value being rerolled would never end.
+                    if !(1..size).contains(&reroll.threshold) {
+                        return Err(invalid(
+                            "must reroll at least 1 and less than the die size",
+                            span,
+                        ));
+                    }
+                    group.reroll = Some(reroll);
+                }
+                Token::Target(target) if group.successes.is_none() => {
+                    // The target must be possible to roll.
+                    let (Target::AtLeast(threshold) | Target::AtMost(threshold)) = target;
+                    if !(1..=size).contains(&threshold) {
+                        return Err(invalid(
+                            "must target at least 1 and at most the die size",
+                            span,
+                        ));
+                    }
+                    group.successes = Some(SuccessRule {
+                        target,
+                        failure: None,
+                    });
+                }
+                Token::Failure(failure) => match &mut group.successes {
+                    // No die can be both a success and a failure.
+                    Some(SuccessRule {
+                        target: Target::AtLeast(threshold),
+                        failure: existing @ None,
+                    }) => {
+                        if !(1..*threshold).contains(&failure) {
+                            return Err(invalid(
+                                "must be at least 1 and below the success target",
+                                span,
+                            ));
+                        }
+                        *existing = Some(failure);
+                    }
+                    Some(SuccessRule { failure: None, .. }) => {
+                        return Err(invalid("needs a success target of the form `>=N`", span));
+                    }
+                    Some(_) => return Err(invalid("is repeated", span)),
+                    None => return Err(invalid("needs a success target before it", span)),
+                },
+                Token::Keep(_) | Token::Explode(_) | Token::Reroll(_) | Token::Target(_) => {
+                    return Err(invalid("is repeated", span));
+                }
+                _ => break,
             }
+            end = span.end;
+            self.next()?;
         }
+        Ok(group)
+    }
+}
+
+/// Convert an error from the lexer into a parse error.
+fn lex_error(error: LexError, span: Span) -> ParseError {
+    let kind = match error {
+        LexError::Unrecognised => ParseErrorKind::Unrecognised,
+        LexError::TooLarge => ParseErrorKind::NumberTooLarge,
+    };
+    ParseError::new(kind, span)
+}
+
+/// Parse a `RollSpec` from a string slice.
+pub fn parse(input: &str) -> Result<RollSpec, ParseError> {
+    let mut parser = Parser::new(input);
+    let spec = parser.sum()?;
+
+    // We must have consumed all the input.
+    if parser.peek().is_some() {
+        return Err(parser.unexpected());
     }
 
     // Check we have at least one die.
-    if dice.is_empty() {
-        return Err(());
+    if spec.dice().is_empty() {
+        return Err(ParseError::new(ParseErrorKind::MissingDie, 0..input.len()));
     }
 
-    Ok(RollSpec { dice, bonuses })
+    Ok(spec)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn dice(count: OutputType, size: OutputType) -> RollSpec {
+        Expr::Dice(DiceGroup::new(count, size))
+    }
+
+    fn num(n: InputType) -> RollSpec {
+        Expr::Number(n)
+    }
+
+    fn add(lhs: RollSpec, rhs: RollSpec) -> RollSpec {
+        Expr::binary(Operator::Add, lhs, rhs)
+    }
+
+    fn sub(lhs: RollSpec, rhs: RollSpec) -> RollSpec {
+        Expr::binary(Operator::Subtract, lhs, rhs)
+    }
+
+    fn mul(lhs: RollSpec, rhs: RollSpec) -> RollSpec {
+        Expr::binary(Operator::Multiply, lhs, rhs)
+    }
+
+    fn div(lhs: RollSpec, rhs: RollSpec) -> RollSpec {
+        Expr::binary(Operator::Divide, lhs, rhs)
+    }
+
+    fn neg(inner: RollSpec) -> RollSpec {
+        Expr::Negate(Box::new(inner))
+    }
+
     #[test]
     fn single_die() {
-        let spec = parse("d4").unwrap();
-        assert_eq!(spec.dice, vec![4]);
-        assert!(spec.bonuses.is_empty());
+        assert_eq!(parse("d4").unwrap(), dice(1, 4));
     }
 
     #[test]
     fn multiple_dice() {
-        let spec = parse("4d8").unwrap();
-        assert_eq!(spec.dice, vec![8, 8, 8, 8]);
-        assert!(spec.bonuses.is_empty());
+        assert_eq!(parse("4d8").unwrap(), dice(4, 8));
     }
 
     #[test]
     fn with_bonus() {
-        let spec = parse("1d10 +2").unwrap();
-        assert_eq!(spec.dice, vec![10]);
-        assert_eq!(spec.bonuses, vec![2]);
+        assert_eq!(parse("1d10 +2").unwrap(), add(dice(1, 10), num(2)));
     }
 
     #[test]
     fn multiple_sizes() {
-        let spec = parse("2d6+1d4").unwrap();
-        assert_eq!(spec.dice, vec![6, 6, 4]);
-        assert!(spec.bonuses.is_empty());
+        assert_eq!(parse("2d6+1d4").unwrap(), add(dice(2, 6), dice(1, 4)));
     }
 
     #[test]
     fn multiple_bonuses() {
-        let spec = parse("1d12 +2+1+1").unwrap();
-        assert_eq!(spec.dice, vec![12]);
-        assert_eq!(spec.bonuses, vec![2, 1, 1]);
+        assert_eq!(
+            parse("1d12 +2+1+1").unwrap(),
+            add(add(add(dice(1, 12), num(2)), num(1)), num(1))
+        );
     }
 
     #[test]
@@ -136,16 +611,13 @@ mod tests {
 
     #[test]
     fn ordering() {
-        let spec = parse("1 + d4").unwrap();
-        assert_eq!(spec.dice, vec![4]);
-        assert_eq!(spec.bonuses, vec![1]);
+        assert_eq!(parse("1 + d4").unwrap(), add(num(1), dice(1, 4)));
     }
 
     #[test]
     fn capitalisation() {
         let spec1 = parse("2d4").unwrap();
-        assert_eq!(spec1.dice, vec![4, 4]);
-        assert!(spec1.bonuses.is_empty());
+        assert_eq!(spec1, dice(2, 4));
 
         let spec2 = parse("2D4").unwrap();
         assert_eq!(spec1, spec2);
@@ -163,7 +635,295 @@ mod tests {
         parse("2d 4").unwrap_err();
         parse("2 d 4").unwrap_err();
         parse("2d4 1d6").unwrap_err();
-        parse("300d4").unwrap_err();
-        parse("d1000").unwrap_err();
+        parse("99999999999d4").unwrap_err();
+        parse("d99999999999").unwrap_err();
+        parse("2d4 +").unwrap_err();
+        parse("2d4 -").unwrap_err();
+        parse("2d4 -+ 1").unwrap_err();
+        parse("2d4 + 100000").unwrap_err();
+        parse("kh1").unwrap_err();
+        parse("2d4 + kh1").unwrap_err();
+        parse("2d4 kh1").unwrap_err();
+        parse("2d4kh3").unwrap_err();
+        parse("2d4kh1kl1").unwrap_err();
+        parse("2d4kx1").unwrap_err();
+        parse("3 kh1").unwrap_err();
+        parse("d6 !").unwrap_err();
+        parse("d6!!").unwrap_err();
+        parse("d6r1r2").unwrap_err();
+        parse("d1!").unwrap_err();
+        parse("d6!>1").unwrap_err();
+        parse("d6!>7").unwrap_err();
+        parse("d6r6").unwrap_err();
+        parse("d6r0").unwrap_err();
+        parse("2d6 + r1").unwrap_err();
+        parse("!").unwrap_err();
+        parse("6d10 >=7").unwrap_err();
+        parse("6d10>=7>=8").unwrap_err();
+        parse("6d10f1").unwrap_err();
+        parse("6d10>=7f1f2").unwrap_err();
+        parse("6d10>=11").unwrap_err();
+        parse("6d10>=0").unwrap_err();
+        parse("6d10>=7f7").unwrap_err();
+        parse("6d10<=3f1").unwrap_err();
+        parse(">=7").unwrap_err();
+        parse("(d6").unwrap_err();
+        parse("d6)").unwrap_err();
+        parse("()").unwrap_err();
+        parse("(5)").unwrap_err();
+        parse("2d6 * ").unwrap_err();
+        parse("2d6 ** 2").unwrap_err();
+        parse("2d6 */ 2").unwrap_err();
+        parse("max d6").unwrap_err();
+        parse("max()").unwrap_err();
+        parse("max(d6,)").unwrap_err();
+        parse("floor(d6, 2)").unwrap_err();
+        parse("maximum(d6)").unwrap_err();
+        parse("d6, d8").unwrap_err();
+        parse("d0").unwrap_err();
+        parse("2d0 + 1").unwrap_err();
+    }
+
+    #[test]
+    fn error_positions() {
+        let error = |spec| {
+            let e = parse(spec).unwrap_err();
+            (e.kind, e.span)
+        };
+        assert_eq!(error(""), (ParseErrorKind::MissingDie, 0..0));
+        assert_eq!(error("5 + 3"), (ParseErrorKind::MissingDie, 0..5));
+        assert_eq!(error("1d4 + fish"), (ParseErrorKind::Unrecognised, 6..7));
+        assert_eq!(error("2d4 ++ 6"), (ParseErrorKind::UnexpectedToken, 5..6));
+        assert_eq!(error("2d4 1d6"), (ParseErrorKind::UnexpectedToken, 4..7));
+        assert_eq!(
+            error("2d4 + 100000"),
+            (ParseErrorKind::NumberTooLarge, 6..12)
+        );
+        assert_eq!(
+            error("99999999999d4"),
+            (ParseErrorKind::NumberTooLarge, 0..13)
+        );
+        assert_eq!(error("2d6 +"), (ParseErrorKind::DanglingOperator, 4..5));
+        assert_eq!(error("(d6 + 1"), (ParseErrorKind::UnclosedParen, 0..1));
+        assert_eq!(error("d6)"), (ParseErrorKind::UnexpectedToken, 2..3));
+        assert_eq!(error("max d6"), (ParseErrorKind::MissingArguments, 0..3));
+        assert_eq!(error("d0"), (ParseErrorKind::NoSides, 0..2));
+        assert_eq!(error("d6 + 3d0!"), (ParseErrorKind::NoSides, 5..8));
+        assert_eq!(
+            error("floor(d6, 2)"),
+            (ParseErrorKind::WrongArgumentCount, 0..12)
+        );
+        for (spec, span) in [("2d4kh3", 3..6), ("6d10>=7f1f2", 9..11)] {
+            let e = parse(spec).unwrap_err();
+            assert!(matches!(e.kind, ParseErrorKind::InvalidModifier(_)));
+            assert_eq!(e.span, span);
+        }
+    }
+
+    #[test]
+    fn nesting() {
+        let nested = |depth| format!("{}d6{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(parse(&nested(MAX_NESTING)).unwrap(), dice(1, 6));
+        // Far deeper input is refused rather than overflowing the stack.
+        for spec in [
+            nested(MAX_NESTING + 1),
+            nested(3000),
+            format!("{}d6", "-".repeat(6000)),
+            format!("{}d6{}", "max(".repeat(1500), ")".repeat(1500)),
+        ] {
+            assert_eq!(parse(&spec).unwrap_err().kind, ParseErrorKind::TooDeep);
+        }
+        assert_eq!(parse(&nested(MAX_NESTING + 1)).unwrap_err().span, 64..65);
+    }
+
+    #[test]
+    fn large_numbers() {
+        // Limits are checked separately, so large rolls parse fine.
+        assert_eq!(parse("300d1000").unwrap(), dice(300, 1000));
+        assert_eq!(parse("d6 + 1000").unwrap(), add(dice(1, 6), num(1000)));
+        assert_eq!(
+            parse("4294967295d4294967295").unwrap(),
+            dice(OutputType::MAX, OutputType::MAX)
+        );
+    }
+
+    #[test]
+    fn error_description() {
+        let describe = |spec| parse(spec).unwrap_err().describe(spec);
+        assert_eq!(
+            describe("2d6 + 1d4 ++ 3"),
+            "`+` was unexpected\n```\n2d6 + 1d4 ++ 3\n           ^\n```"
+        );
+        assert_eq!(
+            describe("d6 + 100000"),
+            "`100000` is too large\n```\nd6 + 100000\n     ^^^^^^\n```"
+        );
+        // Positions are counted in characters, not bytes.
+        assert_eq!(
+            describe("d6 + é"),
+            "`é` is not recognised\n```\nd6 + é\n     ^\n```"
+        );
+    }
+
+    #[test]
+    fn success_pools() {
+        let pool = |spec| parse(spec).unwrap().dice()[0].successes.unwrap();
+        let rule = |target, failure| SuccessRule { target, failure };
+        assert_eq!(pool("6d10>=7"), rule(Target::AtLeast(7), None));
+        assert_eq!(pool("6d10>7"), rule(Target::AtLeast(7), None));
+        assert_eq!(pool("8d6>4f1"), rule(Target::AtLeast(4), Some(1)));
+        assert_eq!(pool("8d6>=4f<=2"), rule(Target::AtLeast(4), Some(2)));
+        assert_eq!(pool("5d10<3"), rule(Target::AtMost(3), None));
+        assert_eq!(pool("5d10<=3"), rule(Target::AtMost(3), None));
+        // Pools can be combined with other modifiers.
+        let spec = parse("6d10!>=8").unwrap();
+        assert_eq!(spec.dice()[0].explode, Some(10));
+        assert_eq!(spec.dice()[0].successes.unwrap().target, Target::AtLeast(8));
+    }
+
+    #[test]
+    fn exploding() {
+        let spec = parse("3d6!").unwrap();
+        assert_eq!(spec.dice()[0].explode, Some(6));
+
+        let spec = parse("3d10!>8 + 1").unwrap();
+        assert_eq!(spec.dice()[0].explode, Some(8));
+    }
+
+    #[test]
+    fn rerolling() {
+        let reroll = |threshold, once| Some(Reroll { threshold, once });
+        assert_eq!(parse("2d6r1").unwrap().dice()[0].reroll, reroll(1, false));
+        assert_eq!(parse("2d6r<2").unwrap().dice()[0].reroll, reroll(2, false));
+        assert_eq!(parse("2d6ro1").unwrap().dice()[0].reroll, reroll(1, true));
+        assert_eq!(parse("2d6RO<3").unwrap().dice()[0].reroll, reroll(3, true));
+    }
+
+    #[test]
+    fn combined_modifiers() {
+        let spec = parse("4d6r1!kh3").unwrap();
+        let group = *spec.dice()[0];
+        assert_eq!(group.reroll.unwrap().threshold, 1);
+        assert_eq!(group.explode, Some(6));
+        assert_eq!(group.keep, Some(KeepRule::KeepHighest(3)));
+        // The order of modifiers doesn't matter.
+        assert_eq!(parse("4d6kh3!r1").unwrap(), spec);
+    }
+
+    #[test]
+    fn keep_and_drop() {
+        let group = |count, size, keep| DiceGroup {
+            keep: Some(keep),
+            ..DiceGroup::new(count, size)
+        };
+
+        let spec = parse("4d6kh3").unwrap();
+        assert_eq!(spec.dice(), vec![&group(4, 6, KeepRule::KeepHighest(3))]);
+
+        let spec = parse("2d20kl1 + 1").unwrap();
+        assert_eq!(spec.dice(), vec![&group(2, 20, KeepRule::KeepLowest(1))]);
+
+        let spec = parse("3d8dh1 + 5d4DL2").unwrap();
+        assert_eq!(
+            spec.dice(),
+            vec![
+                &group(3, 8, KeepRule::DropHighest(1)),
+                &group(5, 4, KeepRule::DropLowest(2)),
+            ]
+        );
+
+        // The count defaults to 1.
+        let spec = parse("2d20kh").unwrap();
+        assert_eq!(spec.dice(), vec![&group(2, 20, KeepRule::KeepHighest(1))]);
+    }
+
+    #[test]
+    fn subtraction() {
+        assert_eq!(parse("2d6 - 1").unwrap(), sub(dice(2, 6), num(1)));
+        assert_eq!(
+            parse("d8 - 2 + 3 - 4").unwrap(),
+            sub(add(sub(dice(1, 8), num(2)), num(3)), num(4))
+        );
+        assert_eq!(parse("2d4 - d6").unwrap(), sub(dice(2, 4), dice(1, 6)));
+    }
+
+    #[test]
+    fn negative_bonuses() {
+        assert_eq!(parse("d10 + -2").unwrap(), add(dice(1, 10), neg(num(2))));
+        assert_eq!(parse("-1 + d4").unwrap(), add(neg(num(1)), dice(1, 4)));
+        assert_eq!(parse("d4 - -1").unwrap(), sub(dice(1, 4), neg(num(1))));
+        assert_eq!(parse("-d6").unwrap(), neg(dice(1, 6)));
+        assert_eq!(parse("--d6").unwrap(), neg(neg(dice(1, 6))));
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            parse("d6 + 2 * 3").unwrap(),
+            add(dice(1, 6), mul(num(2), num(3)))
+        );
+        assert_eq!(
+            parse("d6 * 2 + 3").unwrap(),
+            add(mul(dice(1, 6), num(2)), num(3))
+        );
+        assert_eq!(
+            parse("d12 / 2 * 3").unwrap(),
+            mul(div(dice(1, 12), num(2)), num(3))
+        );
+        assert_eq!(parse("-d6 * 2").unwrap(), mul(neg(dice(1, 6)), num(2)));
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(
+            parse("(2d6+3)*2").unwrap(),
+            mul(add(dice(2, 6), num(3)), num(2))
+        );
+        assert_eq!(
+            parse("d20 - (d4 - 1)").unwrap(),
+            sub(dice(1, 20), sub(dice(1, 4), num(1)))
+        );
+        assert_eq!(parse("((d6))").unwrap(), dice(1, 6));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(
+            parse("max(d20, d20)").unwrap(),
+            Expr::Call(Function::Max, vec![dice(1, 20), dice(1, 20)])
+        );
+        assert_eq!(
+            parse("MIN(d6)").unwrap(),
+            Expr::Call(Function::Min, vec![dice(1, 6)])
+        );
+        assert_eq!(
+            parse("floor(2d6 / 2) + 1").unwrap(),
+            add(
+                Expr::Call(Function::Floor, vec![div(dice(2, 6), num(2))]),
+                num(1)
+            )
+        );
+        assert_eq!(
+            parse("round(d6/2)").unwrap(),
+            Expr::Call(Function::Round, vec![div(dice(1, 6), num(2))])
+        );
+        parse("ceil(d6/3)").unwrap();
+        parse("abs(d6 - d6)").unwrap();
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for input in [
+            "2d6 + 3",
+            "(2d6 + 3) * 2",
+            "1d20 - (1d4 - 1)",
+            "-(1d6 + 1)",
+            "1d4 - -1",
+            "max(1d20, 1d20) + 2",
+            "floor(3d6 / 2)",
+            "4d6r1!kh3 * 2",
+        ] {
+            assert_eq!(parse(input).unwrap().to_string(), input);
+        }
     }
 }
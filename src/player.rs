@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::TypeMapKey;
+
+use crate::character::Character;
+use crate::legacy::{self, Legacy, LegacyKind};
+use crate::rolls::Outcome;
+use crate::store::Store;
+use crate::tracks::ProgressTrack;
+
+/// Everything the bot remembers about a player.
+/// Fields default when missing, so older saved data still loads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Player {
+    pub tracks: Vec<ProgressTrack>,
+    pub legacy: Legacy,
+    /// The player's character sheet, once they've started filling it in.
+    pub character: Option<Character>,
+}
+
+impl Player {
+    /// Complete a progress track after a progress roll with the given outcome.
+    /// Only a hit completes the track, and each track is only rewarded once.
+    /// Returns the legacy track that was marked and by how many ticks, if any.
+    pub fn complete_track(&mut self, index: usize, outcome: Outcome) -> Option<(LegacyKind, u16)> {
+        let track = &mut self.tracks[index];
+        if outcome == Outcome::Miss || track.completed {
+            return None;
+        }
+        track.completed = true;
+        let kind = LegacyKind::rewarded_by(track.kind)?;
+        let ticks = legacy::reward(track.rank, outcome);
+        self.legacy.track_mut(kind).mark(ticks);
+        Some((kind, ticks))
+    }
+}
+
+/// The key for the store of all players.
+pub struct Players;
+
+impl TypeMapKey for Players {
+    type Value = Arc<Store<Player>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracks::{Rank, TrackKind};
+
+    #[test]
+    fn completing_tracks() {
+        let mut player = Player::default();
+        for (name, kind) in [("Vow", TrackKind::Vow), ("Fight", TrackKind::Combat)] {
+            let track = ProgressTrack::new(name.to_string(), kind, Rank::Extreme);
+            player.tracks.push(track);
+        }
+
+        assert_eq!(player.complete_track(0, Outcome::Miss), None);
+        assert!(!player.tracks[0].completed);
+        assert_eq!(
+            player.complete_track(0, Outcome::WeakHit),
+            Some((LegacyKind::Quests, 4))
+        );
+        assert!(player.tracks[0].completed);
+        // A completed track isn't rewarded again.
+        assert_eq!(player.complete_track(0, Outcome::StrongHit), None);
+        assert_eq!(player.legacy.quests.ticks, 4);
+
+        // Combat tracks complete without a reward.
+        assert_eq!(player.complete_track(1, Outcome::StrongHit), None);
+        assert!(player.tracks[1].completed);
+    }
+}
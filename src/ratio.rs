@@ -0,0 +1,235 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use crate::SignedOutputType;
+
+/// An exact rational number.
+/// This lets custom rolls divide without losing precision until they explicitly round.
+/// The denominator is always positive, and the fraction is always in lowest terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numer: SignedOutputType,
+    denom: SignedOutputType,
+}
+
+/// Greatest common divisor, which is always non-negative.
+fn gcd(mut a: SignedOutputType, mut b: SignedOutputType) -> SignedOutputType {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+impl Ratio {
+    /// Zero.
+    pub const ZERO: Self = Self::integer(0);
+
+    /// A whole number.
+    pub const fn integer(n: SignedOutputType) -> Self {
+        Self { numer: n, denom: 1 }
+    }
+
+    /// The fraction `numer / denom`, or `None` if the denominator is zero
+    /// or the result can't be represented.
+    pub fn new(numer: SignedOutputType, denom: SignedOutputType) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let divisor = gcd(numer, denom);
+        let (mut numer, mut denom) = (numer / divisor, denom / divisor);
+        if denom < 0 {
+            numer = numer.checked_neg()?;
+            denom = denom.checked_neg()?;
+        }
+        Some(Self { numer, denom })
+    }
+
+    /// Is this a whole number?
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let numer = self
+            .numer
+            .checked_mul(other.denom)?
+            .checked_add(other.numer.checked_mul(self.denom)?)?;
+        Self::new(numer, self.denom.checked_mul(other.denom)?)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(other.checked_neg()?)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(other.numer)?,
+            self.denom.checked_mul(other.denom)?,
+        )
+    }
+
+    /// Divide, returning `None` when dividing by zero or on overflow.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.numer.checked_mul(other.denom)?,
+            self.denom.checked_mul(other.numer)?,
+        )
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self {
+            numer: self.numer.checked_neg()?,
+            denom: self.denom,
+        })
+    }
+
+    pub fn checked_abs(self) -> Option<Self> {
+        Some(Self {
+            numer: self.numer.checked_abs()?,
+            denom: self.denom,
+        })
+    }
+
+    /// Round down to a whole number.
+    pub fn floor(self) -> Self {
+        Self::integer(self.numer.div_euclid(self.denom))
+    }
+
+    /// Round up to a whole number.
+    pub fn ceil(self) -> Self {
+        let floor = self.numer.div_euclid(self.denom);
+        if self.is_integer() {
+            Self::integer(floor)
+        } else {
+            Self::integer(floor + 1)
+        }
+    }
+
+    /// Round to the nearest whole number, with halves rounding away from zero.
+    pub fn round(self) -> Self {
+        let twice = i128::from(self.numer) * 2;
+        let denom = i128::from(self.denom);
+        // Work with the magnitude so both signs round away from zero.
+        let magnitude = (twice.abs() + denom) / (2 * denom);
+        let rounded = if twice < 0 { -magnitude } else { magnitude };
+        // This can't overflow, since it's no further from zero than the input.
+        Self::integer(rounded as SignedOutputType)
+    }
+
+    /// An approximation of this number as a float.
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+}
+
+impl From<SignedOutputType> for Ratio {
+    fn from(n: SignedOutputType) -> Self {
+        Self::integer(n)
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Denominators are positive, so cross-multiplying preserves the order.
+        let lhs = i128::from(self.numer) * i128::from(other.denom);
+        let rhs = i128::from(other.numer) * i128::from(self.denom);
+        lhs.cmp(&rhs)
+    }
+}
+
+impl Display for Ratio {
+    /// Whole numbers are shown exactly; anything else to two decimal places.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            let decimal = format!("{:.2}", self.to_f64());
+            write!(f, "{}", decimal.trim_end_matches('0').trim_end_matches('.'))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numer: SignedOutputType, denom: SignedOutputType) -> Ratio {
+        Ratio::new(numer, denom).unwrap()
+    }
+
+    #[test]
+    fn normalisation() {
+        assert_eq!(ratio(2, 4), ratio(1, 2));
+        assert_eq!(ratio(3, -6), ratio(-1, 2));
+        assert_eq!(ratio(-3, -6), ratio(1, 2));
+        assert_eq!(ratio(0, -5), Ratio::ZERO);
+        assert_eq!(Ratio::new(1, 0), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let half = ratio(1, 2);
+        let third = ratio(1, 3);
+        assert_eq!(half.checked_add(third), Some(ratio(5, 6)));
+        assert_eq!(half.checked_sub(third), Some(ratio(1, 6)));
+        assert_eq!(half.checked_mul(third), Some(ratio(1, 6)));
+        assert_eq!(half.checked_div(third), Some(ratio(3, 2)));
+        assert_eq!(half.checked_div(Ratio::ZERO), None);
+        assert_eq!(
+            Ratio::integer(SignedOutputType::MAX).checked_add(half),
+            None
+        );
+    }
+
+    #[test]
+    fn rounding() {
+        let cases = [
+            // (numer, denom, floor, ceil, round)
+            (7, 2, 3, 4, 4),
+            (-7, 2, -4, -3, -4),
+            (5, 3, 1, 2, 2),
+            (4, 3, 1, 2, 1),
+            (-4, 3, -2, -1, -1),
+            (6, 3, 2, 2, 2),
+        ];
+        for (numer, denom, floor, ceil, round) in cases {
+            let r = ratio(numer, denom);
+            assert_eq!(
+                r.floor(),
+                Ratio::integer(floor),
+                "floor({}/{})",
+                numer,
+                denom
+            );
+            assert_eq!(r.ceil(), Ratio::integer(ceil), "ceil({}/{})", numer, denom);
+            assert_eq!(
+                r.round(),
+                Ratio::integer(round),
+                "round({}/{})",
+                numer,
+                denom
+            );
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(ratio(1, 3) < ratio(1, 2));
+        assert!(ratio(-1, 2) < ratio(-1, 3));
+        assert!(Ratio::integer(2) > ratio(3, 2));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Ratio::integer(-4).to_string(), "-4");
+        assert_eq!(ratio(7, 2).to_string(), "3.5");
+        assert_eq!(ratio(-1, 3).to_string(), "-0.33");
+        assert_eq!(ratio(5, 4).to_string(), "1.25");
+    }
+}
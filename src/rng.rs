@@ -0,0 +1,47 @@
+use std::sync::{Arc, Mutex};
+
+use rand::{rngs::StdRng, SeedableRng};
+use serenity::prelude::TypeMapKey;
+
+/// The bot-wide source of randomness shared by all commands.
+///
+/// Every roll made by the bot draws from this single generator, so starting
+/// the bot with the same seed and replaying the same commands in the same
+/// order reproduces exactly the same results.
+#[derive(Debug)]
+pub struct RngProvider {
+    seed: u64,
+    rng: Mutex<StdRng>,
+}
+
+impl RngProvider {
+    /// Create a provider from a fixed seed.
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    /// Create a provider with a randomly-chosen seed.
+    pub fn from_entropy() -> Self {
+        Self::seeded(rand::random())
+    }
+
+    /// The seed this provider was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Run the given function with exclusive access to the generator.
+    pub fn with<T>(&self, f: impl FnOnce(&mut StdRng) -> T) -> T {
+        // A panic while holding the lock cannot leave the generator in an
+        // invalid state, so it's fine to ignore poisoning.
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut rng)
+    }
+}
+
+impl TypeMapKey for RngProvider {
+    type Value = Arc<RngProvider>;
+}
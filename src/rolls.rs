@@ -1,19 +1,37 @@
-use std::cmp::{min, Ordering};
+use std::cmp::{min, Reverse};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use rand::Rng;
 
-use crate::{InputType, OutputType};
+use crate::{
+    character::Stat,
+    expr::{EvalError, Expr, Precedence, Term},
+    ratio::Ratio,
+    InputType, OutputType, SignedOutputType,
+};
 
 /// The outcome of an action or progress roll.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Outcome {
     Miss,
     WeakHit,
     StrongHit,
 }
 
+impl Outcome {
+    /// The outcome of a score against the challenge dice.
+    /// Each challenge die is beaten only if the score is strictly higher.
+    fn of(score: OutputType, challenge_dice: [OutputType; 2]) -> Self {
+        match challenge_dice.iter().filter(|&&c| score > c).count() {
+            0 => Self::Miss,
+            1 => Self::WeakHit,
+            2 => Self::StrongHit,
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl Display for Outcome {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let name = match self {
@@ -25,97 +43,210 @@ impl Display for Outcome {
     }
 }
 
+/// The numeric type used for momentum, which may be negative.
+pub type Momentum = i8;
+/// The lowest that momentum can go.
+pub const MIN_MOMENTUM: Momentum = -6;
+/// The highest that momentum can go.
+pub const MAX_MOMENTUM: Momentum = 10;
+
+/// The dice used for action and progress rolls, which depend on the ruleset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dice {
+    /// The number of sides on the action die.
+    pub action_die: OutputType,
+    /// The number of sides on each challenge die.
+    pub challenge_die: OutputType,
+    /// The highest that a score can be, however large the bonus.
+    pub max_score: OutputType,
+    /// Whether the second challenge die is the cursed die.
+    pub cursed_die: bool,
+}
+
+/// The Starforged dice.
+impl Default for Dice {
+    fn default() -> Self {
+        Self {
+            action_die: 6,
+            challenge_die: 10,
+            max_score: 10,
+            cursed_die: false,
+        }
+    }
+}
+
+impl Dice {
+    /// Roll the two challenge dice.
+    fn challenge_dice<R: Rng + ?Sized>(&self, rng: &mut R) -> [OutputType; 2] {
+        [
+            rng.gen_range(1..=self.challenge_die),
+            rng.gen_range(1..=self.challenge_die),
+        ]
+    }
+
+    /// Did these challenge dice give a cursed result?
+    /// This happens when the cursed die shows its highest face.
+    fn is_cursed(&self, challenge_dice: [OutputType; 2]) -> bool {
+        self.cursed_die && challenge_dice[1] == self.challenge_die
+    }
+
+    /// Write out the challenge dice, marking the cursed die if there is one.
+    fn fmt_challenge_dice(&self, challenge_dice: [OutputType; 2]) -> String {
+        let cursed = if self.cursed_die { "☠" } else { "" };
+        format!("[{}] {}[{}]", challenge_dice[0], cursed, challenge_dice[1])
+    }
+}
+
 /// The result of an action roll.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ActionRoll {
+    pub dice: Dice,
     pub action_die: OutputType,
     pub bonus: Option<InputType>,
+    /// The stat included in the bonus, if it came from a character.
+    pub stat: Option<Stat>,
+    /// The player's momentum at the time of the roll, if given.
+    pub momentum: Option<Momentum>,
     pub challenge_dice: [OutputType; 2],
 }
 
 impl ActionRoll {
-    /// Generate a random action roll.
-    pub fn random(bonus: impl Into<Option<InputType>>) -> Self {
-        let mut rng = rand::thread_rng();
-        let action_die = rng.gen_range(1..=6);
-        let challenge_dice = [rng.gen_range(1..=10), rng.gen_range(1..=10)];
+    /// Generate an action roll using the given source of randomness.
+    pub fn random<R: Rng + ?Sized>(
+        rng: &mut R,
+        dice: Dice,
+        bonus: impl Into<Option<InputType>>,
+        momentum: impl Into<Option<Momentum>>,
+    ) -> Self {
+        let action_die = rng.gen_range(1..=dice.action_die);
+        let challenge_dice = dice.challenge_dice(rng);
         Self {
+            dice,
             action_die,
             bonus: bonus.into(),
+            stat: None,
+            momentum: momentum.into(),
             challenge_dice,
         }
     }
 
+    /// Is the action die cancelled by negative momentum?
+    /// This happens when the die matches the momentum's magnitude, e.g. a 2 at -2 momentum.
+    pub fn is_cancelled(&self) -> bool {
+        self.momentum
+            .is_some_and(|m| m < 0 && OutputType::from(m.unsigned_abs()) == self.action_die)
+    }
+
     /// What is the total score of this roll?
     /// Only known if the bonus is known.
     pub fn score(&self) -> Option<OutputType> {
-        Some(min(self.action_die + u32::from(self.bonus?), 10))
+        let action = if self.is_cancelled() {
+            0
+        } else {
+            self.action_die
+        };
+        Some(min(
+            action + OutputType::from(self.bonus?),
+            self.dice.max_score,
+        ))
     }
 
     /// What is the outcome of this roll?
     /// Only known if the bonus is known.
     pub fn outcome(&self) -> Option<Outcome> {
-        let score = self.score()?;
-        let mut higher_than = 0;
-        for challenge in self.challenge_dice {
-            if score > challenge {
-                higher_than += 1;
-            }
-        }
-        Some(match higher_than {
-            0 => Outcome::Miss,
-            1 => Outcome::WeakHit,
-            2 => Outcome::StrongHit,
-            _ => unreachable!(),
-        })
+        Some(Outcome::of(self.score()?, self.challenge_dice))
+    }
+
+    /// What would the outcome be after burning momentum?
+    /// Only known if burning momentum would improve the outcome.
+    pub fn burn_outcome(&self) -> Option<Outcome> {
+        let momentum = OutputType::try_from(self.momentum?).ok()?;
+        let burnt = Outcome::of(momentum, self.challenge_dice);
+        (burnt > self.outcome()?).then_some(burnt)
     }
 
     /// Do the challenge dice match?
     pub fn is_match(&self) -> bool {
         self.challenge_dice[0] == self.challenge_dice[1]
     }
-}
 
-impl Display for ActionRoll {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    /// Did the cursed die give a cursed result?
+    pub fn is_cursed(&self) -> bool {
+        self.dice.is_cursed(self.challenge_dice)
+    }
+
+    /// Write this roll under the given title, such as the name of a move.
+    pub fn fmt_titled(&self, f: &mut Formatter<'_>, title: &str) -> std::fmt::Result {
+        let title = match self.stat {
+            Some(stat) => format!("{} ({})", title, stat),
+            None => title.to_string(),
+        };
+        // A cancelled action die is struck through, like a dropped die.
+        let action_die = if self.is_cancelled() {
+            format!("[~~{}~~]", self.action_die)
+        } else {
+            format!("[{}]", self.action_die)
+        };
+        let challenge_dice = self.dice.fmt_challenge_dice(self.challenge_dice);
         if let Some(bonus) = self.bonus {
+            let burn = match self.burn_outcome() {
+                Some(outcome) => format!(
+                    "; burn {} momentum for a {}",
+                    self.momentum.unwrap(),
+                    outcome
+                ),
+                None => String::new(),
+            };
             write!(
                 f,
-                "***Action Roll: [{}]+{} = {} vs [{}] [{}] ({}{})***",
-                self.action_die,
+                "***{}: {}+{} = {} vs {} ({}{}{}{})***",
+                title,
+                action_die,
                 bonus,
                 self.score().unwrap(),
-                self.challenge_dice[0],
-                self.challenge_dice[1],
+                challenge_dice,
                 if self.is_match() { "Matched " } else { "" },
-                self.outcome().unwrap()
+                self.outcome().unwrap(),
+                if self.is_cursed() { "; Cursed" } else { "" },
+                burn
             )
         } else {
             write!(
                 f,
-                "***Action Roll: [{}] vs [{}] [{}]{}***",
-                self.action_die,
-                self.challenge_dice[0],
-                self.challenge_dice[1],
-                if self.is_match() { " (Match)" } else { "" }
+                "***{}: {} vs {}{}***",
+                title,
+                action_die,
+                challenge_dice,
+                unresolved_notes(self.is_match(), self.is_cursed())
             )
         }
     }
 }
 
+impl Display for ActionRoll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_titled(f, "Action Roll")
+    }
+}
+
 /// The result of a progress roll.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ProgressRoll {
+    pub dice: Dice,
     pub bonus: Option<InputType>,
     pub challenge_dice: [OutputType; 2],
 }
 
 impl ProgressRoll {
-    /// Generate a random progress roll.
-    pub fn random(bonus: impl Into<Option<InputType>>) -> Self {
-        let mut rng = rand::thread_rng();
-        let challenge_dice = [rng.gen_range(1..=10), rng.gen_range(1..=10)];
+    /// Generate a progress roll using the given source of randomness.
+    pub fn random<R: Rng + ?Sized>(
+        rng: &mut R,
+        dice: Dice,
+        bonus: impl Into<Option<InputType>>,
+    ) -> Self {
+        let challenge_dice = dice.challenge_dice(rng);
         Self {
+            dice,
             bonus: bonus.into(),
             challenge_dice,
         }
@@ -124,57 +255,60 @@ impl ProgressRoll {
     /// What is the total score of this roll?
     /// Only known if the bonus is known.
     pub fn score(&self) -> Option<OutputType> {
-        Some(min(self.bonus?.into(), 10))
+        Some(min(self.bonus?.into(), self.dice.max_score))
     }
 
     /// What is the outcome of this roll?
     /// Only known if the bonus is known.
     pub fn outcome(&self) -> Option<Outcome> {
-        let score = self.score()?;
-        let mut higher_than = 0;
-        for challenge in self.challenge_dice {
-            if score > challenge {
-                higher_than += 1;
-            }
-        }
-        Some(match higher_than {
-            0 => Outcome::Miss,
-            1 => Outcome::WeakHit,
-            2 => Outcome::StrongHit,
-            _ => unreachable!(),
-        })
+        Some(Outcome::of(self.score()?, self.challenge_dice))
     }
 
     /// Do the challenge dice match?
     pub fn is_match(&self) -> bool {
         self.challenge_dice[0] == self.challenge_dice[1]
     }
+
+    /// Did the cursed die give a cursed result?
+    pub fn is_cursed(&self) -> bool {
+        self.dice.is_cursed(self.challenge_dice)
+    }
 }
 
 impl Display for ProgressRoll {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let challenge_dice = self.dice.fmt_challenge_dice(self.challenge_dice);
         if self.bonus.is_some() {
             write!(
                 f,
-                "***Progress Roll: {} vs [{}] [{}] ({}{})***",
+                "***Progress Roll: {} vs {} ({}{}{})***",
                 self.score().unwrap(),
-                self.challenge_dice[0],
-                self.challenge_dice[1],
+                challenge_dice,
                 if self.is_match() { "Matched " } else { "" },
-                self.outcome().unwrap()
+                self.outcome().unwrap(),
+                if self.is_cursed() { "; Cursed" } else { "" }
             )
         } else {
             write!(
                 f,
-                "***Progress Roll: [{}] [{}]{}***",
-                self.challenge_dice[0],
-                self.challenge_dice[1],
-                if self.is_match() { " (Match)" } else { "" }
+                "***Progress Roll: {}{}***",
+                challenge_dice,
+                unresolved_notes(self.is_match(), self.is_cursed())
             )
         }
     }
 }
 
+/// Notes on the challenge dice of a roll whose outcome isn't known.
+fn unresolved_notes(is_match: bool, is_cursed: bool) -> &'static str {
+    match (is_match, is_cursed) {
+        (true, true) => " (Match; Cursed)",
+        (true, false) => " (Match)",
+        (false, true) => " (Cursed)",
+        (false, false) => "",
+    }
+}
+
 /// The result of an oracle roll.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OracleRoll {
@@ -182,9 +316,8 @@ pub struct OracleRoll {
 }
 
 impl OracleRoll {
-    /// Generate a random oracle roll.
-    pub fn random(num: usize) -> Self {
-        let mut rng = rand::thread_rng();
+    /// Generate an oracle roll using the given source of randomness.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, num: usize) -> Self {
         let mut outcomes = Vec::with_capacity(num);
         for _ in 0..num {
             outcomes.push(rng.gen_range(1..=100));
@@ -204,16 +337,285 @@ impl Display for OracleRoll {
     }
 }
 
-/// The specification for a custom roll.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct RollSpec {
-    pub dice: Vec<InputType>,
-    pub bonuses: Vec<InputType>,
+/// How likely a yes answer is when asking the oracle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Odds {
+    SmallChance,
+    Unlikely,
+    FiftyFifty,
+    Likely,
+    AlmostCertain,
 }
 
-impl FromStr for RollSpec {
+impl Odds {
+    /// The highest d100 roll that answers yes.
+    pub fn threshold(&self) -> OutputType {
+        match self {
+            Self::SmallChance => 10,
+            Self::Unlikely => 25,
+            Self::FiftyFifty => 50,
+            Self::Likely => 75,
+            Self::AlmostCertain => 90,
+        }
+    }
+}
+
+impl FromStr for Odds {
     type Err = ();
 
+    /// Parse some odds from their name, ignoring case and punctuation,
+    /// so `almost certain`, `almost-certain`, and `50/50` all work.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let odds = match name.as_str() {
+            "smallchance" | "small" => Self::SmallChance,
+            "unlikely" => Self::Unlikely,
+            "5050" | "fiftyfifty" | "even" => Self::FiftyFifty,
+            "likely" => Self::Likely,
+            "almostcertain" | "certain" => Self::AlmostCertain,
+            _ => return Err(()),
+        };
+        Ok(odds)
+    }
+}
+
+impl Display for Odds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::SmallChance => "Small Chance",
+            Self::Unlikely => "Unlikely",
+            Self::FiftyFifty => "50/50",
+            Self::Likely => "Likely",
+            Self::AlmostCertain => "Almost Certain",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The result of asking the oracle a yes/no question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AskRoll {
+    pub odds: Odds,
+    pub roll: OutputType,
+}
+
+impl AskRoll {
+    /// Generate an answer using the given source of randomness.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, odds: Odds) -> Self {
+        Self {
+            odds,
+            roll: rng.gen_range(1..=100),
+        }
+    }
+
+    /// Is the answer yes?
+    pub fn is_yes(&self) -> bool {
+        self.roll <= self.odds.threshold()
+    }
+
+    /// Are both digits of the roll the same? 100 counts as double zero.
+    pub fn is_match(&self) -> bool {
+        self.roll == 100 || self.roll / 10 == self.roll % 10
+    }
+}
+
+impl Display for AskRoll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "***Ask the Oracle ({}): [{}] ({}{}{})***",
+            self.odds,
+            self.roll,
+            if self.is_match() { "Matched " } else { "" },
+            if self.is_yes() { "Yes" } else { "No" },
+            if self.is_match() {
+                "; an extreme result or twist"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// A rule for which dice in a group count towards its total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepRule {
+    /// Keep only this many of the highest dice.
+    KeepHighest(OutputType),
+    /// Keep only this many of the lowest dice.
+    KeepLowest(OutputType),
+    /// Drop this many of the highest dice.
+    DropHighest(OutputType),
+    /// Drop this many of the lowest dice.
+    DropLowest(OutputType),
+}
+
+impl KeepRule {
+    /// Which of the given rolls should be dropped under this rule?
+    /// Where rolls are tied, the later ones are dropped first.
+    fn dropped(&self, rolls: &[OutputType]) -> Vec<bool> {
+        // Indices of the rolls from lowest to highest.
+        let mut ascending = (0..rolls.len()).collect::<Vec<_>>();
+        ascending.sort_by_key(|&i| (rolls[i], Reverse(i)));
+        // Indices of the rolls from highest to lowest.
+        let mut descending = (0..rolls.len()).collect::<Vec<_>>();
+        descending.sort_by_key(|&i| (Reverse(rolls[i]), Reverse(i)));
+
+        let (order, num_dropped) = match *self {
+            Self::KeepHighest(n) => (ascending, rolls.len().saturating_sub(n as usize)),
+            Self::KeepLowest(n) => (descending, rolls.len().saturating_sub(n as usize)),
+            Self::DropHighest(n) => (descending, n as usize),
+            Self::DropLowest(n) => (ascending, n as usize),
+        };
+        let mut dropped = vec![false; rolls.len()];
+        for i in order.into_iter().take(num_dropped) {
+            dropped[i] = true;
+        }
+        dropped
+    }
+}
+
+impl Display for KeepRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KeepHighest(n) => write!(f, "kh{}", n),
+            Self::KeepLowest(n) => write!(f, "kl{}", n),
+            Self::DropHighest(n) => write!(f, "dh{}", n),
+            Self::DropLowest(n) => write!(f, "dl{}", n),
+        }
+    }
+}
+
+/// A rule for rerolling low dice, e.g. `r1` or `ro<2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reroll {
+    /// Dice showing this value or lower are rerolled.
+    pub threshold: OutputType,
+    /// Whether to reroll only once, rather than until the die is above the threshold.
+    pub once: bool,
+}
+
+impl Display for Reroll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.once {
+            write!(f, "ro{}", self.threshold)
+        } else {
+            write!(f, "r{}", self.threshold)
+        }
+    }
+}
+
+/// Which dice count as successes in a dice pool, e.g. `>=7` or `<3`.
+/// As with the other modifiers, the threshold itself is included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// Dice showing this value or higher succeed.
+    AtLeast(OutputType),
+    /// Dice showing this value or lower succeed.
+    AtMost(OutputType),
+}
+
+impl Target {
+    /// Does the given roll meet this target?
+    pub fn is_met(&self, roll: OutputType) -> bool {
+        match *self {
+            Self::AtLeast(n) => roll >= n,
+            Self::AtMost(n) => roll <= n,
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AtLeast(n) => write!(f, ">={}", n),
+            Self::AtMost(n) => write!(f, "<={}", n),
+        }
+    }
+}
+
+/// The rules for counting successes in a dice pool, e.g. `8d6>=4f1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuccessRule {
+    pub target: Target,
+    /// Dice showing this value or lower are failures, which cancel out successes.
+    pub failure: Option<OutputType>,
+}
+
+impl Display for SuccessRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.target)?;
+        if let Some(failure) = self.failure {
+            write!(f, "f{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+/// The maximum number of extra dice that a single die may add by rerolling or exploding.
+/// This guarantees that every roll terminates, however unlucky.
+pub const MAX_EXTRA_DICE: usize = 100;
+
+/// A group of identically-sized dice, e.g. `4d6kh3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiceGroup {
+    pub count: OutputType,
+    pub size: OutputType,
+    /// Dice showing this value or higher explode, adding another die to the group.
+    pub explode: Option<OutputType>,
+    pub reroll: Option<Reroll>,
+    pub keep: Option<KeepRule>,
+    /// If present, this group is a dice pool that counts successes instead of summing.
+    pub successes: Option<SuccessRule>,
+}
+
+impl DiceGroup {
+    /// A group of dice without any modifiers.
+    pub fn new(count: OutputType, size: OutputType) -> Self {
+        Self {
+            count,
+            size,
+            explode: None,
+            reroll: None,
+            keep: None,
+            successes: None,
+        }
+    }
+}
+
+impl Display for DiceGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}d{}", self.count, self.size)?;
+        if let Some(reroll) = self.reroll {
+            write!(f, "{}", reroll)?;
+        }
+        match self.explode {
+            Some(explode) if explode == self.size => write!(f, "!")?,
+            Some(explode) => write!(f, "!>{}", explode)?,
+            None => {}
+        }
+        if let Some(keep) = self.keep {
+            write!(f, "{}", keep)?;
+        }
+        if let Some(successes) = self.successes {
+            write!(f, "{}", successes)?;
+        }
+        Ok(())
+    }
+}
+
+impl Term for DiceGroup {}
+
+/// The specification for a custom roll: an arithmetic expression involving dice.
+pub type RollSpec = Expr<DiceGroup>;
+
+impl FromStr for RollSpec {
+    type Err = crate::parse_roll_spec::ParseError;
+
     /// Parse a `RollSpec` from a string like `3d6+5`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         crate::parse_roll_spec::parse(s)
@@ -225,96 +627,728 @@ impl FromStr for RollSpec {
 pub struct RolledDie {
     pub size: OutputType,
     pub roll: OutputType,
+    /// Whether this die was dropped, and so does not count towards the total.
+    pub dropped: bool,
+    /// Whether this die was rerolled, and so was replaced by the next die.
+    pub rerolled: bool,
+    /// Whether this die exploded, and so was followed by an extra die.
+    pub exploded: bool,
+    /// Whether this die counted as a success in a dice pool.
+    pub success: bool,
+    /// Whether this die counted as a failure in a dice pool.
+    pub failure: bool,
 }
 
-/// The result of a custom roll.
+impl RolledDie {
+    /// A die that was rolled without any modifiers applying.
+    pub fn new(size: OutputType, roll: OutputType) -> Self {
+        Self {
+            size,
+            roll,
+            dropped: false,
+            rerolled: false,
+            exploded: false,
+            success: false,
+            failure: false,
+        }
+    }
+
+    /// Does this die count towards the total?
+    pub fn counts(&self) -> bool {
+        !(self.dropped || self.rerolled)
+    }
+}
+
+impl Display for RolledDie {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let explosion = if self.exploded { "!" } else { "" };
+        let result = if self.success {
+            "✓"
+        } else if self.failure {
+            "✗"
+        } else {
+            ""
+        };
+        if self.counts() {
+            write!(f, "[{}{}{}]", self.roll, explosion, result)
+        } else {
+            write!(f, "[~~{}{}~~]", self.roll, explosion)
+        }
+    }
+}
+
+/// The result of rolling a group of dice.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct CustomRoll {
-    /// All rolled dice, in descending order of size.
+pub struct RolledGroup {
+    pub group: DiceGroup,
     pub rolls: Vec<RolledDie>,
-    /// The total bonus.
-    pub bonus: OutputType,
 }
 
-impl CustomRoll {
-    /// Perform a custom roll.
-    pub fn random(spec: RollSpec) -> Self {
-        let mut rng = rand::thread_rng();
+impl RolledGroup {
+    /// Roll a group of dice using the given source of randomness.
+    /// Every die rolled is recorded, including those that were rerolled or dropped.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, group: DiceGroup) -> Self {
+        let size = group.size;
         let mut rolls = Vec::new();
-        for die in spec.dice {
-            let roll = rng.gen_range(1..=die).into();
-            rolls.push(RolledDie {
-                size: die.into(),
-                roll,
-            });
-        }
-        rolls.sort_unstable_by(|a, b| b.size.cmp(&a.size));
-        let bonus = spec.bonuses.into_iter().map(Into::<OutputType>::into).sum();
-        Self { rolls, bonus }
-    }
-
-    /// Get the list of all dice in this roll, e.g. `[2d4, 1d6, 5d8]`.
-    /// This is returned as a list of `(count, size)` pairs.
-    /// We depend on the invariant that `self.rolls` is in descending size order.
-    pub fn dice(&self) -> Vec<(OutputType, OutputType)> {
-        let mut dice = Vec::new();
-        let mut size = OutputType::MAX;
-        for die in &self.rolls {
-            match die.size.cmp(&size) {
-                Ordering::Less => {
-                    dice.push((1, die.size));
-                    size = die.size;
-                }
-                Ordering::Equal => {
-                    let current = &mut dice.last_mut().unwrap().0;
-                    *current += 1;
+        for _ in 0..group.count {
+            let mut extra_dice = 0;
+            // Whether the current die has already been rerolled.
+            let mut rerolled = false;
+            loop {
+                let mut die = RolledDie::new(size, rng.gen_range(1..=size));
+                if extra_dice < MAX_EXTRA_DICE {
+                    if let Some(reroll) = group.reroll {
+                        if die.roll <= reroll.threshold && !(reroll.once && rerolled) {
+                            die.rerolled = true;
+                            rerolled = true;
+                        }
+                    }
+                    if let Some(explode) = group.explode {
+                        if !die.rerolled && die.roll >= explode {
+                            die.exploded = true;
+                            rerolled = false;
+                        }
+                    }
                 }
-                Ordering::Greater => {
-                    panic!("self.rolls was not in descending order!");
+                rolls.push(die);
+                if !(die.rerolled || die.exploded) {
+                    break;
                 }
+                extra_dice += 1;
             }
         }
-        dice
+
+        // Drop any dice as required, ignoring those that were rerolled.
+        if let Some(keep) = group.keep {
+            let counted = rolls
+                .iter_mut()
+                .filter(|die| !die.rerolled)
+                .collect::<Vec<_>>();
+            let values = counted.iter().map(|die| die.roll).collect::<Vec<_>>();
+            for (die, dropped) in counted.into_iter().zip(keep.dropped(&values)) {
+                die.dropped = dropped;
+            }
+        }
+
+        // Mark successes and failures if this is a dice pool.
+        if let Some(successes) = group.successes {
+            for die in rolls.iter_mut().filter(|die| die.counts()) {
+                die.success = successes.target.is_met(die.roll);
+                die.failure = successes.failure.is_some_and(|failure| die.roll <= failure);
+            }
+        }
+
+        Self { group, rolls }
+    }
+
+    /// Is this group a dice pool that counts successes?
+    pub fn is_pool(&self) -> bool {
+        self.group.successes.is_some()
+    }
+
+    /// The total of this group.
+    /// For a dice pool, this is the number of successes minus the number of failures;
+    /// otherwise it's the sum of all dice that count.
+    pub fn total(&self) -> SignedOutputType {
+        let counted = self.rolls.iter().filter(|die| die.counts());
+        if self.is_pool() {
+            counted
+                .map(|die| {
+                    SignedOutputType::from(die.success) - SignedOutputType::from(die.failure)
+                })
+                .sum()
+        } else {
+            counted.map(|die| SignedOutputType::from(die.roll)).sum()
+        }
+    }
+}
+
+impl Display for RolledGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rolls = self
+            .rolls
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "{}", rolls.join(" "))
+    }
+}
+
+impl Term for RolledGroup {
+    /// Several dice shown together read like a sum.
+    fn precedence(&self) -> Precedence {
+        if self.rolls.len() > 1 {
+            Precedence::Sum
+        } else {
+            Precedence::Atom
+        }
+    }
+}
+
+/// A rolled group of dice collapsed down to its total, for summarising large rolls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CollapsedGroup {
+    dice: usize,
+    total: SignedOutputType,
+}
+
+impl Display for CollapsedGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{} dice: {}]", self.dice, self.total)
+    }
+}
+
+impl Term for CollapsedGroup {}
+
+/// The result of a custom roll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomRoll {
+    /// The rolled expression, with each group of dice replaced by its results.
+    pub expr: Expr<RolledGroup>,
+}
+
+impl CustomRoll {
+    /// Perform a custom roll using the given source of randomness.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, spec: RollSpec) -> Self {
+        let expr = spec.map_dice(&mut |group| RolledGroup::random(rng, *group));
+        Self { expr }
+    }
+
+    /// The specification that was rolled.
+    pub fn spec(&self) -> RollSpec {
+        self.expr.map_dice(&mut |rolled| rolled.group)
+    }
+
+    /// The total of this roll.
+    /// This can fail if, for example, the roll divides by zero.
+    pub fn total(&self) -> Result<Ratio, EvalError> {
+        self.expr.evaluate(&|group| Ratio::integer(group.total()))
+    }
+
+    /// Does this roll count successes rather than summing its dice?
+    /// This is the case if every group is a dice pool.
+    pub fn counts_successes(&self) -> bool {
+        self.expr.dice().into_iter().all(RolledGroup::is_pool)
+    }
+
+    /// A shorter version of this roll's display, with each group of dice
+    /// collapsed to its total.
+    pub fn summary(&self) -> String {
+        let collapsed = self.expr.map_dice(&mut |rolled| CollapsedGroup {
+            dice: rolled.rolls.len(),
+            total: rolled.total(),
+        });
+        self.describe(&collapsed)
+    }
+
+    /// Describe this roll, showing the given results.
+    fn describe(&self, results: &impl Display) -> String {
+        // Show the specification, then the results in the same shape.
+        let mut string = format!("Roll {}:  {}", self.spec(), results);
+
+        // Add the total (only if there was more than one contributor).
+        // Dice pools always report their number of successes.
+        let single_die = matches!(&self.expr, Expr::Dice(group) if group.rolls.len() == 1);
+        match self.total() {
+            Ok(total) if self.counts_successes() => {
+                string.push_str(&format!("  (Successes: {})", total));
+            }
+            Ok(total) if !single_die => string.push_str(&format!("  (Total: {})", total)),
+            Ok(_) => {}
+            Err(e) => string.push_str(&format!("  ({})", e)),
+        }
+
+        format!("***{}***", string)
     }
 }
 
 impl Display for CustomRoll {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut string = vec!["Roll".to_string()];
+        write!(f, "{}", self.describe(&self.expr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
 
-        // Assemble string representing the roll.
-        for (count, size) in self.dice() {
-            string.push(format!(" {}d{}", count, size));
-            string.push(" +".to_string());
+    use super::*;
+    use crate::expr::{Function, Operator};
+
+    /// All the individual dice in a custom roll.
+    fn rolls(roll: &CustomRoll) -> impl Iterator<Item = &RolledDie> {
+        roll.expr.dice().into_iter().flat_map(|group| &group.rolls)
+    }
+
+    #[test]
+    fn seeded_rolls_repeat() {
+        let mut rng1 = StdRng::seed_from_u64(42);
+        let mut rng2 = StdRng::seed_from_u64(42);
+        for _ in 0..10 {
+            assert_eq!(
+                ActionRoll::random(&mut rng1, Dice::default(), 2, 5),
+                ActionRoll::random(&mut rng2, Dice::default(), 2, 5)
+            );
+            assert_eq!(
+                ProgressRoll::random(&mut rng1, Dice::default(), 5),
+                ProgressRoll::random(&mut rng2, Dice::default(), 5)
+            );
+            assert_eq!(
+                OracleRoll::random(&mut rng1, 3),
+                OracleRoll::random(&mut rng2, 3)
+            );
+            let spec: RollSpec = "2d6 + d4 + 1".parse().unwrap();
+            assert_eq!(
+                CustomRoll::random(&mut rng1, spec.clone()),
+                CustomRoll::random(&mut rng2, spec)
+            );
         }
+    }
 
-        // Add the bonus if nonzero.
-        if self.bonus > 0 {
-            string.push(format!(" {}", self.bonus));
-        } else {
-            // Remove the trailing " +".
-            string.pop().unwrap();
+    #[test]
+    fn rolls_in_range() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let action = ActionRoll::random(&mut rng, Dice::default(), None, None);
+            assert!((1..=6).contains(&action.action_die));
+            assert!(action.challenge_dice.iter().all(|d| (1..=10).contains(d)));
+
+            let oracle = OracleRoll::random(&mut rng, 2);
+            assert_eq!(oracle.outcomes.len(), 2);
+            assert!(oracle.outcomes.iter().all(|o| (1..=100).contains(o)));
         }
-        string.push(": ".to_string());
+    }
+
+    #[test]
+    fn asking_the_oracle() {
+        let ask = |odds, roll| AskRoll { odds, roll };
+        assert!(ask(Odds::Likely, 75).is_yes());
+        assert!(!ask(Odds::Likely, 76).is_yes());
+        assert!(ask(Odds::SmallChance, 10).is_yes());
+        assert!(!ask(Odds::AlmostCertain, 91).is_yes());
+        assert!(ask(Odds::FiftyFifty, 33).is_match());
+        assert!(ask(Odds::FiftyFifty, 100).is_match());
+        assert!(!ask(Odds::FiftyFifty, 10).is_match());
+
+        assert_eq!("almost certain".parse(), Ok(Odds::AlmostCertain));
+        assert_eq!("Small-Chance".parse(), Ok(Odds::SmallChance));
+        assert_eq!("50/50".parse(), Ok(Odds::FiftyFifty));
+        assert_eq!("maybe".parse::<Odds>(), Err(()));
+
+        assert_eq!(
+            ask(Odds::Likely, 42).to_string(),
+            "***Ask the Oracle (Likely): [42] (Yes)***"
+        );
+        assert_eq!(
+            ask(Odds::Unlikely, 77).to_string(),
+            "***Ask the Oracle (Unlikely): [77] (Matched No; an extreme result or twist)***"
+        );
 
-        // Add the results.
-        let mut total = 0;
-        for roll in &self.rolls {
-            total += roll.roll;
-            string.push(format!(" [{}]", roll.roll));
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert!((1..=100).contains(&AskRoll::random(&mut rng, Odds::Likely).roll));
         }
+    }
+
+    #[test]
+    fn action_outcomes() {
+        let roll = |action_die, bonus, challenge_dice| ActionRoll {
+            dice: Dice::default(),
+            action_die,
+            bonus: Some(bonus),
+            stat: None,
+            momentum: None,
+            challenge_dice,
+        };
+        assert_eq!(roll(3, 1, [4, 6]).outcome(), Some(Outcome::Miss));
+        assert_eq!(roll(3, 2, [4, 6]).outcome(), Some(Outcome::WeakHit));
+        assert_eq!(roll(5, 2, [4, 6]).outcome(), Some(Outcome::StrongHit));
+        // Scores are capped at 10, so a 10 on the challenge dice can't be beaten.
+        assert_eq!(roll(6, 9, [10, 10]).outcome(), Some(Outcome::Miss));
+        assert!(roll(6, 9, [10, 10]).is_match());
+    }
+
+    #[test]
+    fn momentum() {
+        let roll = |action_die, momentum, challenge_dice| ActionRoll {
+            dice: Dice::default(),
+            action_die,
+            bonus: Some(2),
+            stat: None,
+            momentum: Some(momentum),
+            challenge_dice,
+        };
+        // Burning momentum is only suggested when it improves the outcome.
+        assert_eq!(roll(3, 8, [4, 7]).outcome(), Some(Outcome::WeakHit));
+        assert_eq!(roll(3, 8, [4, 7]).burn_outcome(), Some(Outcome::StrongHit));
+        assert_eq!(roll(3, 7, [4, 7]).burn_outcome(), None);
+        assert_eq!(roll(6, 10, [9, 9]).burn_outcome(), Some(Outcome::StrongHit));
+        assert_eq!(roll(1, 2, [1, 1]).burn_outcome(), None);
+        assert_eq!(roll(1, -2, [1, 1]).burn_outcome(), None);
+        // Negative momentum cancels a matching action die.
+        assert!(roll(4, -4, [3, 5]).is_cancelled());
+        assert_eq!(roll(4, -4, [3, 5]).score(), Some(2));
+        assert_eq!(roll(4, -4, [3, 5]).outcome(), Some(Outcome::Miss));
+        assert!(!roll(4, -3, [3, 5]).is_cancelled());
+        assert!(!roll(4, 4, [3, 5]).is_cancelled());
+    }
 
-        // Add the bonus.
-        if self.bonus > 0 {
-            total += self.bonus;
-            string.push(format!(" + {}", self.bonus));
+    #[test]
+    fn action_roll_display() {
+        let roll = |action_die, bonus, momentum, challenge_dice| ActionRoll {
+            dice: Dice::default(),
+            action_die,
+            bonus,
+            stat: None,
+            momentum,
+            challenge_dice,
+        };
+        assert_eq!(
+            roll(3, Some(2), None, [4, 7]).to_string(),
+            "***Action Roll: [3]+2 = 5 vs [4] [7] (Weak Hit)***"
+        );
+        assert_eq!(
+            roll(3, Some(2), Some(8), [4, 4]).to_string(),
+            "***Action Roll: [3]+2 = 5 vs [4] [4] (Matched Strong Hit)***"
+        );
+        assert_eq!(
+            roll(3, Some(2), Some(8), [4, 7]).to_string(),
+            "***Action Roll: [3]+2 = 5 vs [4] [7] (Weak Hit; burn 8 momentum for a Strong Hit)***"
+        );
+        assert_eq!(
+            roll(2, Some(1), Some(-2), [1, 9]).to_string(),
+            "***Action Roll: [~~2~~]+1 = 1 vs [1] [9] (Miss)***"
+        );
+        assert_eq!(
+            roll(2, None, Some(-2), [1, 9]).to_string(),
+            "***Action Roll: [~~2~~] vs [1] [9]***"
+        );
+        let with_stat = ActionRoll {
+            stat: Some(Stat::Wits),
+            ..roll(3, Some(3), None, [4, 7])
+        };
+        assert_eq!(
+            with_stat.to_string(),
+            "***Action Roll (Wits): [3]+3 = 6 vs [4] [7] (Weak Hit)***"
+        );
+    }
+
+    #[test]
+    fn progress_outcomes() {
+        let roll = |bonus, challenge_dice| ProgressRoll {
+            dice: Dice::default(),
+            bonus: Some(bonus),
+            challenge_dice,
+        };
+        assert_eq!(roll(0, [1, 1]).outcome(), Some(Outcome::Miss));
+        assert_eq!(roll(5, [5, 4]).outcome(), Some(Outcome::WeakHit));
+        assert_eq!(roll(12, [9, 3]).outcome(), Some(Outcome::StrongHit));
+        assert_eq!(roll(12, [9, 3]).score(), Some(10));
+    }
+
+    #[test]
+    fn cursed_die() {
+        let dice = Dice {
+            cursed_die: true,
+            ..Dice::default()
+        };
+        let action = |bonus, challenge_dice| ActionRoll {
+            dice,
+            action_die: 4,
+            bonus,
+            stat: None,
+            momentum: None,
+            challenge_dice,
+        };
+        // Only a 10 on the cursed die itself is cursed.
+        assert!(action(Some(2), [3, 10]).is_cursed());
+        assert!(!action(Some(2), [10, 3]).is_cursed());
+        assert_eq!(action(Some(2), [3, 10]).outcome(), Some(Outcome::WeakHit));
+        assert_eq!(
+            action(Some(2), [3, 10]).to_string(),
+            "***Action Roll: [4]+2 = 6 vs [3] ☠[10] (Weak Hit; Cursed)***"
+        );
+        assert_eq!(
+            action(Some(2), [10, 10]).to_string(),
+            "***Action Roll: [4]+2 = 6 vs [10] ☠[10] (Matched Miss; Cursed)***"
+        );
+        assert_eq!(
+            action(None, [5, 6]).to_string(),
+            "***Action Roll: [4] vs [5] ☠[6]***"
+        );
+        assert_eq!(
+            action(None, [10, 10]).to_string(),
+            "***Action Roll: [4] vs [10] ☠[10] (Match; Cursed)***"
+        );
+
+        let progress = |bonus, challenge_dice| ProgressRoll {
+            dice,
+            bonus,
+            challenge_dice,
+        };
+        assert_eq!(
+            progress(Some(7), [2, 10]).to_string(),
+            "***Progress Roll: 7 vs [2] ☠[10] (Weak Hit; Cursed)***"
+        );
+        assert_eq!(
+            progress(None, [2, 10]).to_string(),
+            "***Progress Roll: [2] ☠[10] (Cursed)***"
+        );
+        // Without the cursed die, a 10 is just a 10.
+        let plain = ProgressRoll {
+            dice: Dice::default(),
+            ..progress(Some(7), [2, 10])
+        };
+        assert!(!plain.is_cursed());
+        assert_eq!(
+            plain.to_string(),
+            "***Progress Roll: 7 vs [2] [10] (Weak Hit)***"
+        );
+    }
+
+    #[test]
+    fn custom_roll_display() {
+        let group = |count, size, rolls: &[OutputType]| {
+            Expr::Dice(RolledGroup {
+                group: DiceGroup::new(count, size),
+                rolls: rolls
+                    .iter()
+                    .map(|&roll| RolledDie::new(size, roll))
+                    .collect(),
+            })
+        };
+        let dice = || Expr::binary(Operator::Add, group(2, 6, &[4, 1]), group(1, 4, &[2]));
+        let roll = |expr| CustomRoll { expr }.to_string();
+
+        assert_eq!(
+            roll(dice()),
+            "***Roll 2d6 + 1d4:  [4] [1] + [2]  (Total: 7)***"
+        );
+        assert_eq!(
+            roll(Expr::binary(Operator::Add, dice(), Expr::Number(3))),
+            "***Roll 2d6 + 1d4 + 3:  [4] [1] + [2] + 3  (Total: 10)***"
+        );
+        assert_eq!(
+            roll(Expr::binary(Operator::Subtract, dice(), Expr::Number(9))),
+            "***Roll 2d6 + 1d4 - 9:  [4] [1] + [2] - 9  (Total: -2)***"
+        );
+        assert_eq!(
+            roll(Expr::binary(Operator::Multiply, dice(), Expr::Number(2))),
+            "***Roll (2d6 + 1d4) * 2:  ([4] [1] + [2]) * 2  (Total: 14)***"
+        );
+        // Several dice in one group are bracketed when they need to be.
+        assert_eq!(
+            roll(Expr::binary(
+                Operator::Divide,
+                group(2, 6, &[4, 1]),
+                Expr::Number(2)
+            )),
+            "***Roll 2d6 / 2:  ([4] [1]) / 2  (Total: 2.5)***"
+        );
+        assert_eq!(
+            roll(Expr::Call(
+                Function::Floor,
+                vec![Expr::binary(
+                    Operator::Divide,
+                    group(1, 6, &[5]),
+                    Expr::Number(2)
+                )]
+            )),
+            "***Roll floor(1d6 / 2):  floor([5] / 2)  (Total: 2)***"
+        );
+        assert_eq!(
+            roll(Expr::Call(
+                Function::Max,
+                vec![group(1, 20, &[3]), group(1, 20, &[17])]
+            )),
+            "***Roll max(1d20, 1d20):  max([3], [17])  (Total: 17)***"
+        );
+        // A single die doesn't need a total.
+        assert_eq!(roll(group(1, 20, &[12])), "***Roll 1d20:  [12]***");
+        // Impossible calculations are reported.
+        assert_eq!(
+            roll(Expr::binary(
+                Operator::Divide,
+                Expr::Number(1),
+                Expr::binary(Operator::Subtract, group(1, 2, &[1]), Expr::Number(1))
+            )),
+            "***Roll 1 / (1d2 - 1):  1 / ([1] - 1)  (Division by zero)***"
+        );
+    }
+
+    #[test]
+    fn custom_roll_summary() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let spec: RollSpec = "300d100 + 2 * 5d6".parse().unwrap();
+        let roll = CustomRoll::random(&mut rng, spec);
+        let groups = roll.expr.dice();
+        let summary = roll.summary();
+        assert_eq!(
+            summary,
+            format!(
+                "***Roll 300d100 + 2 * 5d6:  [300 dice: {}] + 2 * [5 dice: {}]  (Total: {})***",
+                groups[0].total(),
+                groups[1].total(),
+                roll.total().unwrap()
+            )
+        );
+        assert!(summary.len() < roll.to_string().len());
+    }
+
+    #[test]
+    fn keep_rules() {
+        let rolls = [3, 6, 1, 6, 2];
+        let dropped = |keep: KeepRule| keep.dropped(&rolls);
+        assert_eq!(
+            dropped(KeepRule::KeepHighest(3)),
+            [false, false, true, false, true]
+        );
+        assert_eq!(
+            dropped(KeepRule::KeepLowest(2)),
+            [true, true, false, true, false]
+        );
+        // Ties drop the later die first.
+        assert_eq!(
+            dropped(KeepRule::DropHighest(1)),
+            [false, false, false, true, false]
+        );
+        assert_eq!(
+            dropped(KeepRule::DropLowest(2)),
+            [false, false, true, false, true]
+        );
+        assert_eq!(dropped(KeepRule::KeepHighest(5)), [false; 5]);
+    }
+
+    #[test]
+    fn dropped_dice_excluded() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let spec: RollSpec = "4d6kh3 + 2d20dl1 + 1".parse().unwrap();
+        for _ in 0..100 {
+            let roll = CustomRoll::random(&mut rng, spec.clone());
+            assert_eq!(rolls(&roll).filter(|die| die.dropped).count(), 2);
+            let kept = rolls(&roll)
+                .filter(|die| die.counts())
+                .map(|die| SignedOutputType::from(die.roll))
+                .sum::<SignedOutputType>();
+            assert_eq!(roll.total(), Ok(Ratio::integer(kept + 1)));
         }
+    }
 
-        // Add the total (only if there was more than one contributor).
-        if self.rolls.len() > 1 || self.bonus > 0 {
-            string.push(format!("  (Total: {})", total));
+    #[test]
+    fn exploding_dice() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let spec: RollSpec = "10d6!>5".parse().unwrap();
+        for _ in 0..100 {
+            let roll = CustomRoll::random(&mut rng, spec.clone());
+            let rolls = rolls(&roll).collect::<Vec<_>>();
+            // Every die at or above the threshold explodes into another die.
+            let num_exploded = rolls.iter().filter(|die| die.exploded).count();
+            assert_eq!(rolls.len(), 10 + num_exploded);
+            for die in &rolls {
+                assert_eq!(die.exploded, die.roll >= 5);
+            }
+            assert!(!rolls.last().unwrap().exploded);
+            let sum = rolls.iter().map(|die| die.roll).sum::<OutputType>();
+            assert_eq!(roll.total(), Ok(Ratio::integer(sum.into())));
         }
+    }
 
-        write!(f, "***{}***", string.join(""))
+    #[test]
+    fn rerolling_dice() {
+        let mut rng = StdRng::seed_from_u64(4);
+
+        // Recursive rerolls never leave a counted die at or below the threshold.
+        let spec: RollSpec = "10d6r2".parse().unwrap();
+        for _ in 0..100 {
+            let roll = CustomRoll::random(&mut rng, spec.clone());
+            let counted = rolls(&roll).filter(|die| die.counts()).collect::<Vec<_>>();
+            assert_eq!(counted.len(), 10);
+            assert!(counted.iter().all(|die| die.roll > 2));
+            assert!(rolls(&roll)
+                .filter(|die| die.rerolled)
+                .all(|die| die.roll <= 2));
+        }
+
+        // Rerolling once never rerolls twice in a row.
+        let spec: RollSpec = "10d6ro3".parse().unwrap();
+        for _ in 0..100 {
+            let roll = CustomRoll::random(&mut rng, spec.clone());
+            let rolls = rolls(&roll).collect::<Vec<_>>();
+            assert_eq!(rolls.iter().filter(|die| die.counts()).count(), 10);
+            for pair in rolls.windows(2) {
+                assert!(!(pair[0].rerolled && pair[1].rerolled));
+            }
+        }
+    }
+
+    #[test]
+    fn modified_dice_display() {
+        let mut group = DiceGroup::new(3, 6);
+        group.explode = Some(6);
+        group.reroll = Some(Reroll {
+            threshold: 1,
+            once: true,
+        });
+        group.keep = Some(KeepRule::KeepHighest(2));
+        assert_eq!(group.to_string(), "3d6ro1!kh2");
+        group.explode = Some(5);
+        group.reroll = None;
+        assert_eq!(group.to_string(), "3d6!>5kh2");
+
+        let mut die = RolledDie::new(6, 6);
+        die.exploded = true;
+        assert_eq!(die.to_string(), "[6!]");
+        die.dropped = true;
+        assert_eq!(die.to_string(), "[~~6!~~]");
+        let mut die = RolledDie::new(6, 1);
+        die.rerolled = true;
+        assert_eq!(die.to_string(), "[~~1~~]");
+    }
+
+    #[test]
+    fn success_pools() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let spec: RollSpec = "8d6>=4f1".parse().unwrap();
+        for _ in 0..100 {
+            let roll = CustomRoll::random(&mut rng, spec.clone());
+            assert!(roll.counts_successes());
+            let successes = rolls(&roll).filter(|die| die.roll >= 4).count();
+            let failures = rolls(&roll).filter(|die| die.roll == 1).count();
+            let expected = successes as SignedOutputType - failures as SignedOutputType;
+            assert_eq!(roll.total(), Ok(Ratio::integer(expected)));
+        }
+
+        // Mixing pools with ordinary dice gives an ordinary total.
+        let spec: RollSpec = "6d10>=7 + 1d6".parse().unwrap();
+        let roll = CustomRoll::random(&mut rng, spec);
+        assert!(!roll.counts_successes());
+    }
+
+    #[test]
+    fn success_pool_display() {
+        let mut group = DiceGroup::new(3, 10);
+        group.successes = Some(SuccessRule {
+            target: Target::AtLeast(7),
+            failure: Some(1),
+        });
+        let die = |roll, success, failure| RolledDie {
+            success,
+            failure,
+            ..RolledDie::new(10, roll)
+        };
+        let roll = CustomRoll {
+            expr: Expr::Dice(RolledGroup {
+                group,
+                rolls: vec![
+                    die(8, true, false),
+                    die(1, false, true),
+                    die(9, true, false),
+                ],
+            }),
+        };
+        assert_eq!(
+            roll.to_string(),
+            "***Roll 3d10>=7f1:  [8✓] [1✗] [9✓]  (Successes: 1)***"
+        );
     }
 }
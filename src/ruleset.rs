@@ -0,0 +1,323 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::TypeMapKey;
+
+use crate::moves::Moves;
+use crate::oracles::Oracles;
+use crate::rolls::{Dice, Outcome};
+
+// The data for each ruleset, in formats based on Datasworn.
+// Sundered Isles shares the Starforged moves and core oracles.
+const STARFORGED_CORE_ORACLES: &str = include_str!("../data/starforged/core_oracles.json");
+const STARFORGED_ORACLES: &str = include_str!("../data/starforged/oracles.json");
+const STARFORGED_MOVES: &str = include_str!("../data/starforged/moves.json");
+const CLASSIC_ORACLES: &str = include_str!("../data/classic/oracles.json");
+const CLASSIC_MOVES: &str = include_str!("../data/classic/moves.json");
+const SUNDERED_ISLES_ORACLES: &str = include_str!("../data/sundered_isles/oracles.json");
+
+/// The rules that a game is played by: the dice rolled, what their outcomes mean,
+/// the moves, and the oracles.
+pub trait Ruleset: Send + Sync {
+    /// Which ruleset this is.
+    fn id(&self) -> RulesetId;
+
+    /// The dice used for action and progress rolls, and the highest score they allow.
+    fn dice(&self) -> Dice;
+
+    /// What matching challenge dice mean for an outcome, when the move doesn't say.
+    fn match_guidance(&self, outcome: Outcome) -> &'static str;
+
+    /// Can a challenge die be replaced with the cursed die?
+    fn has_cursed_die(&self) -> bool {
+        false
+    }
+
+    /// The moves that can be made.
+    fn moves(&self) -> &Moves;
+
+    /// The oracle tables that can be rolled on.
+    fn oracles(&self) -> &Oracles;
+}
+
+/// Load bundled oracle tables, which are known to be valid.
+fn bundled_oracles(files: &[&str]) -> Oracles {
+    Oracles::load(files).unwrap_or_else(|e| panic!("Error loading oracles: {}", e))
+}
+
+/// Load bundled moves, which are known to be valid.
+fn bundled_moves(json: &str) -> Moves {
+    Moves::from_json(json).unwrap_or_else(|e| panic!("Error loading moves: {}", e))
+}
+
+/// What matching challenge dice mean in Starforged, which Sundered Isles shares.
+fn starforged_match_guidance(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::StrongHit => "An unexpected opportunity or advantage; envision how it helps you.",
+        Outcome::WeakHit => "An unexpected detail or complication; envision how it changes things.",
+        Outcome::Miss => "A dire threat or twist; envision how things take a turn for the worse.",
+    }
+}
+
+/// Ironsworn: Starforged, the bot's original ruleset.
+pub struct Starforged {
+    moves: Moves,
+    oracles: Oracles,
+}
+
+impl Default for Starforged {
+    fn default() -> Self {
+        Self {
+            moves: bundled_moves(STARFORGED_MOVES),
+            oracles: bundled_oracles(&[STARFORGED_CORE_ORACLES, STARFORGED_ORACLES]),
+        }
+    }
+}
+
+impl Ruleset for Starforged {
+    fn id(&self) -> RulesetId {
+        RulesetId::Starforged
+    }
+
+    fn dice(&self) -> Dice {
+        Dice::default()
+    }
+
+    fn match_guidance(&self, outcome: Outcome) -> &'static str {
+        starforged_match_guidance(outcome)
+    }
+
+    fn moves(&self) -> &Moves {
+        &self.moves
+    }
+
+    fn oracles(&self) -> &Oracles {
+        &self.oracles
+    }
+}
+
+/// The original Ironsworn, set in the Ironlands.
+pub struct IronswornClassic {
+    moves: Moves,
+    oracles: Oracles,
+}
+
+impl Default for IronswornClassic {
+    fn default() -> Self {
+        Self {
+            moves: bundled_moves(CLASSIC_MOVES),
+            oracles: bundled_oracles(&[CLASSIC_ORACLES]),
+        }
+    }
+}
+
+impl Ruleset for IronswornClassic {
+    fn id(&self) -> RulesetId {
+        RulesetId::IronswornClassic
+    }
+
+    fn dice(&self) -> Dice {
+        Dice {
+            action_die: 6,
+            challenge_die: 10,
+            max_score: 10,
+            cursed_die: false,
+        }
+    }
+
+    // Classic only singles out matches on strong hits and misses.
+    fn match_guidance(&self, outcome: Outcome) -> &'static str {
+        match outcome {
+            Outcome::StrongHit => "You succeed with an unexpected advantage; envision what it is.",
+            Outcome::WeakHit => "Something interesting happens; envision what it is.",
+            Outcome::Miss => "You face a dire threat or complication; envision what goes wrong.",
+        }
+    }
+
+    fn moves(&self) -> &Moves {
+        &self.moves
+    }
+
+    fn oracles(&self) -> &Oracles {
+        &self.oracles
+    }
+}
+
+/// Sundered Isles, which builds on the Starforged moves and core oracles.
+pub struct SunderedIsles {
+    moves: Moves,
+    oracles: Oracles,
+}
+
+impl Default for SunderedIsles {
+    fn default() -> Self {
+        Self {
+            moves: bundled_moves(STARFORGED_MOVES),
+            oracles: bundled_oracles(&[STARFORGED_CORE_ORACLES, SUNDERED_ISLES_ORACLES]),
+        }
+    }
+}
+
+impl Ruleset for SunderedIsles {
+    fn id(&self) -> RulesetId {
+        RulesetId::SunderedIsles
+    }
+
+    fn dice(&self) -> Dice {
+        Dice {
+            action_die: 6,
+            challenge_die: 10,
+            max_score: 10,
+            cursed_die: false,
+        }
+    }
+
+    fn match_guidance(&self, outcome: Outcome) -> &'static str {
+        starforged_match_guidance(outcome)
+    }
+
+    fn has_cursed_die(&self) -> bool {
+        true
+    }
+
+    fn moves(&self) -> &Moves {
+        &self.moves
+    }
+
+    fn oracles(&self) -> &Oracles {
+        &self.oracles
+    }
+}
+
+/// Identifies a ruleset, so that it can be chosen and saved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RulesetId {
+    #[default]
+    Starforged,
+    IronswornClassic,
+    SunderedIsles,
+}
+
+impl RulesetId {
+    pub const ALL: [Self; 3] = [
+        Self::Starforged,
+        Self::IronswornClassic,
+        Self::SunderedIsles,
+    ];
+}
+
+impl FromStr for RulesetId {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace(['-', '_'], " ").as_str() {
+            "starforged" => Ok(Self::Starforged),
+            "ironsworn" | "classic" | "ironsworn classic" => Ok(Self::IronswornClassic),
+            "sundered isles" | "sundered" | "si" => Ok(Self::SunderedIsles),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for RulesetId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Starforged => "Starforged",
+            Self::IronswornClassic => "Ironsworn Classic",
+            Self::SunderedIsles => "Sundered Isles",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Every ruleset that the bot supports.
+pub struct Rulesets {
+    rulesets: Vec<Arc<dyn Ruleset>>,
+}
+
+impl Rulesets {
+    /// Load all the rulesets that come with the bot.
+    pub fn bundled() -> Self {
+        Self {
+            rulesets: vec![
+                Arc::new(Starforged::default()),
+                Arc::new(IronswornClassic::default()),
+                Arc::new(SunderedIsles::default()),
+            ],
+        }
+    }
+
+    /// Get a ruleset by its id.
+    pub fn get(&self, id: RulesetId) -> Arc<dyn Ruleset> {
+        self.rulesets
+            .iter()
+            .find(|ruleset| ruleset.id() == id)
+            .cloned()
+            .unwrap_or_else(|| panic!("Ruleset {} missing", id))
+    }
+}
+
+impl TypeMapKey for Rulesets {
+    type Value = Arc<Rulesets>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_rulesets() {
+        let rulesets = Rulesets::bundled();
+        for id in RulesetId::ALL {
+            let ruleset = rulesets.get(id);
+            assert_eq!(ruleset.id(), id);
+            assert_eq!(ruleset.dice(), Dice::default());
+            // Every ruleset has the core oracles and moves.
+            for name in ["Action", "Theme", "Pay the Price"] {
+                assert_eq!(ruleset.oracles().find(name).unwrap().sides(), 100);
+            }
+            assert!(ruleset.moves().find("Face Danger").is_some());
+        }
+
+        let classic = rulesets.get(RulesetId::IronswornClassic);
+        assert!(classic.oracles().find("Region").is_some());
+        assert!(classic.oracles().find("Planet Type").is_none());
+        assert!(classic.moves().find("Enter the Fray").is_some());
+        let price = classic.oracles().find("Pay the Price").unwrap();
+        assert!(price.lookup(1).unwrap().text.starts_with("Roll again"));
+        assert!(price
+            .lookup(100)
+            .unwrap()
+            .text
+            .starts_with("Roll twice more"));
+        let isles = rulesets.get(RulesetId::SunderedIsles);
+        assert!(isles.has_cursed_die());
+        assert!(!classic.has_cursed_die());
+        assert!(isles.oracles().find("Ship Type").is_some());
+        assert!(isles.oracles().find("Stellar Object").is_none());
+
+        // Classic has its own guidance for matches.
+        let starforged = rulesets.get(RulesetId::Starforged);
+        assert_ne!(
+            classic.match_guidance(Outcome::Miss),
+            starforged.match_guidance(Outcome::Miss)
+        );
+        assert_eq!(
+            isles.match_guidance(Outcome::Miss),
+            starforged.match_guidance(Outcome::Miss)
+        );
+    }
+
+    #[test]
+    fn names() {
+        for id in RulesetId::ALL {
+            assert_eq!(id.to_string().parse(), Ok(id));
+        }
+        assert_eq!("classic".parse(), Ok(RulesetId::IronswornClassic));
+        assert_eq!("Sundered-Isles".parse(), Ok(RulesetId::SunderedIsles));
+        assert_eq!("si".parse(), Ok(RulesetId::SunderedIsles));
+        assert_eq!("starsmith".parse::<RulesetId>(), Err(()));
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::TypeMapKey;
+
+use crate::ruleset::RulesetId;
+use crate::store::Store;
+
+/// How the bot behaves in a guild.
+/// Fields default when missing, so older saved data still loads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The oracle tables rolled on whenever the challenge dice match, if any.
+    pub match_oracle: Option<String>,
+    /// The ruleset used in every channel without its own.
+    pub ruleset: RulesetId,
+    /// Rulesets chosen for particular channels, by channel id.
+    pub channel_rulesets: BTreeMap<u64, RulesetId>,
+}
+
+impl Settings {
+    /// The ruleset used in the given channel.
+    pub fn ruleset_in(&self, channel: u64) -> RulesetId {
+        self.channel_rulesets
+            .get(&channel)
+            .copied()
+            .unwrap_or(self.ruleset)
+    }
+}
+
+impl Display for Settings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "**Match oracle**: {}\n**Ruleset**: {}",
+            self.match_oracle.as_deref().unwrap_or("none"),
+            self.ruleset
+        )?;
+        for (channel, ruleset) in &self.channel_rulesets {
+            write!(f, "\n<#{}>: {}", channel, ruleset)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key for the store of every guild's settings.
+pub struct GuildSettings;
+
+impl TypeMapKey for GuildSettings {
+    type Value = Arc<Store<Settings>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let settings: Settings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, Settings::default());
+        assert_eq!(
+            settings.to_string(),
+            "**Match oracle**: none\n**Ruleset**: Starforged"
+        );
+        let settings = Settings {
+            match_oracle: Some("Pay the Price".to_string()),
+            ..Settings::default()
+        };
+        assert_eq!(
+            settings.to_string(),
+            "**Match oracle**: Pay the Price\n**Ruleset**: Starforged"
+        );
+    }
+
+    #[test]
+    fn channel_rulesets() {
+        let mut settings = Settings {
+            ruleset: RulesetId::IronswornClassic,
+            ..Settings::default()
+        };
+        settings
+            .channel_rulesets
+            .insert(42, RulesetId::SunderedIsles);
+        assert_eq!(settings.ruleset_in(42), RulesetId::SunderedIsles);
+        assert_eq!(settings.ruleset_in(7), RulesetId::IronswornClassic);
+        assert_eq!(
+            settings.to_string(),
+            "**Match oracle**: none\n**Ruleset**: Ironsworn Classic\n<#42>: Sundered Isles"
+        );
+
+        // Saved settings keep their channel rulesets.
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+    }
+}
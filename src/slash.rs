@@ -0,0 +1,511 @@
+use serde_json::Value;
+use serenity::builder::CreateApplicationCommands;
+use serenity::client::Context;
+use serenity::model::application::command::{Command, CommandOptionType};
+use serenity::model::application::interaction::{
+    application_command::{ApplicationCommandInteraction, CommandDataOption},
+    InteractionResponseType,
+};
+
+use crate::character::Stat;
+use crate::output::Response;
+use crate::rolls::{Momentum, MAX_MOMENTUM, MIN_MOMENTUM};
+use crate::{ActionArgs, InputType, Origin, Progress};
+
+/// The highest progress score, once a track is full.
+const MAX_PROGRESS: InputType = 10;
+
+/// The guide to the slash commands, which work even where the bot can't read messages.
+const HELP_TEXT: &str = "***Starforged Bot Guide***
+*This bot helps you make all the rolls you need. It supports the following slash commands:*
+
+Action Rolls (`/action`):
+   Roll an action d6 against the challenge 2d10.
+   `bonus`: your total bonus, i.e. your stat plus any adds; this will tell you the outcome.
+   `stat`: a stat to add from your character sheet, along with your momentum.
+   `momentum`: your momentum, to find out whether burning it would help; \
+negative momentum cancels a matching action die.
+   `move`: the move you're making, which also tells you what happens on the outcome.
+   `cursed`: in Sundered Isles, roll the cursed die in place of a challenge die; \
+a 10 on it is a cursed result.
+   Matching challenge dice come with a prompt for what the match means.
+   Example: `/action bonus:3`, `/action stat:Wits move:Face Danger`
+
+Progress Rolls (`/progress`):
+   Roll your progress against the challenge 2d10.
+   `score`: your progress amount, i.e. the number of filled boxes; this will tell you the outcome.
+   `track`: one of your progress tracks, which a hit completes, marking the reward on your legacy.
+   `cursed`: in Sundered Isles, roll the cursed die in place of a challenge die.
+   Example: `/progress score:9`, `/progress track:Find the lost ship`
+
+Oracle Rolls (`/oracle`):
+   Roll a d100 to pick from an oracle table.
+   `tables`: one or more tables to look up the result on, or `list` to see them all.
+   Results that ask for more rolls, such as rolling twice, are rolled too.
+   `count`: a number of bare d100s to roll at once instead.
+   Example: `/oracle tables:action theme`, `/oracle count:3`
+
+Custom rolls (`/roll`):
+   `spec`: any dice and bonuses you want, using the format `XdY + Z`.
+   You may combine any number of dice and numbers using `+`, `-`, `*`, `/`, \
+and parentheses, as well as the functions `max`, `min`, `floor`, `ceil`, `round`, and `abs`.
+   Add `khN`/`klN` to keep only the highest/lowest N dice, or `dhN`/`dlN` \
+to drop the highest/lowest N dice.
+   Add `!` to explode dice on their maximum (or `!>N` to explode on N or \
+higher), `rN` to reroll dice showing N or lower, or `roN` to reroll them only once.
+   Add `>=N` (or `<=N`) to count successes at or above (or below) N instead \
+of adding up the dice, and `fN` to subtract a failure for each die showing N or lower.
+   Example: `/roll spec:2d4 + 1`, `/roll spec:4d6kh3`, `/roll spec:8d6>=4f1`
+
+Note that there are limits on how many dice and oracles you can roll at once, \
+how many sides dice can have, and how large bonuses can be.
+
+*Other commands:*
+
+Help (`/help`):
+   Display this message.
+
+Download (`/download`):
+   Download the entire history of the channel as a text file.
+   This file is created transiently upon request and is not stored by the bot.";
+
+impl From<&ApplicationCommandInteraction> for Origin {
+    fn from(command: &ApplicationCommandInteraction) -> Self {
+        Self {
+            guild: command.guild_id,
+            channel: command.channel_id,
+            user: command.user.id,
+        }
+    }
+}
+
+/// Register the slash commands with Discord, replacing any registered before.
+pub async fn register(ctx: &Context) -> serenity::Result<()> {
+    Command::set_global_application_commands(&ctx.http, define).await?;
+    Ok(())
+}
+
+/// Define every slash command and its options.
+fn define(commands: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    commands
+        .create_application_command(|command| {
+            command
+                .name("action")
+                .description("Roll an action die against the challenge dice")
+                .create_option(|option| {
+                    option
+                        .name("bonus")
+                        .description("Your total bonus, e.g. your stat plus any adds")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .max_int_value(InputType::MAX)
+                })
+                .create_option(|option| {
+                    option
+                        .name("stat")
+                        .description("A stat to add from your character sheet")
+                        .kind(CommandOptionType::String);
+                    for stat in Stat::ALL {
+                        option.add_string_choice(stat, stat);
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("momentum")
+                        .description("Your momentum, if not your character's")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(MIN_MOMENTUM)
+                        .max_int_value(MAX_MOMENTUM)
+                })
+                .create_option(|option| {
+                    option
+                        .name("move")
+                        .description("The move you're making")
+                        .kind(CommandOptionType::String)
+                })
+                .create_option(|option| {
+                    option
+                        .name("cursed")
+                        .description("Roll the cursed die in place of a challenge die")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_application_command(|command| {
+            command
+                .name("progress")
+                .description("Roll your progress against the challenge dice")
+                .create_option(|option| {
+                    option
+                        .name("score")
+                        .description("Your progress score, i.e. the number of filled boxes")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .max_int_value(MAX_PROGRESS)
+                })
+                .create_option(|option| {
+                    option
+                        .name("track")
+                        .description("One of your progress tracks, to roll and complete")
+                        .kind(CommandOptionType::String)
+                })
+                .create_option(|option| {
+                    option
+                        .name("cursed")
+                        .description("Roll the cursed die in place of a challenge die")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_application_command(|command| {
+            command
+                .name("oracle")
+                .description("Roll on oracle tables, or roll bare d100s")
+                .create_option(|option| {
+                    option
+                        .name("tables")
+                        .description("The tables to roll on, or `list` to see them all")
+                        .kind(CommandOptionType::String)
+                })
+                .create_option(|option| {
+                    option
+                        .name("count")
+                        .description("How many bare d100s to roll")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .max_int_value(InputType::MAX)
+                })
+        })
+        .create_application_command(|command| {
+            command
+                .name("roll")
+                .description("Roll any dice and bonuses you want")
+                .create_option(|option| {
+                    option
+                        .name("spec")
+                        .description("What to roll, e.g. `2d6 + 3` or `4d6kh3`")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+        .create_application_command(|command| {
+            command
+                .name("download")
+                .description("Download the entire history of the channel as a text file")
+        })
+        .create_application_command(|command| {
+            command.name("help").description("Show what the bot can do")
+        })
+}
+
+/// Respond to a slash command.
+/// Mistakes in the options are only shown to the user who made them.
+pub async fn handle(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> serenity::Result<()> {
+    let options = &command.data.options;
+    let response = match command.data.name.as_str() {
+        "action" => action(ctx, command).await,
+        "progress" => match progress_args(options) {
+            Ok((progress, cursed)) => crate::progress_response(ctx, command, progress, cursed)
+                .await
+                .map(Response::paginated),
+            Err(e) => Err(e),
+        },
+        "oracle" => oracle(ctx, command).await,
+        "roll" => match string_option(options, "spec") {
+            Some(spec_raw) => crate::custom_roll_response(ctx, spec_raw.trim()).await,
+            None => Err("Not enough arguments (expected a roll)".to_string()),
+        },
+        "download" => return download(ctx, command).await,
+        "help" => {
+            let response = Response::paginated(HELP_TEXT.to_string());
+            return respond(ctx, command, response, true).await;
+        }
+        name => Err(format!("Unknown command: /{}", name)),
+    };
+    match response {
+        Ok(response) => respond(ctx, command, response, false).await,
+        Err(e) => respond(ctx, command, Response::Messages(vec![e]), true).await,
+    }
+}
+
+/// Make an action roll, for a move if given.
+async fn action(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Result<Response, String> {
+    let options = &command.data.options;
+    let args = action_args(options)?;
+    let ruleset = crate::ruleset(ctx, command).await;
+    let action = match string_option(options, "move") {
+        Some(name) => Some(
+            ruleset
+                .moves()
+                .find(name)
+                .ok_or_else(|| crate::unknown_move(name))?,
+        ),
+        None => None,
+    };
+    crate::action_response(ctx, command, args, action)
+        .await
+        .map(Response::paginated)
+}
+
+/// Roll on oracle tables, or list them, or roll bare d100s.
+async fn oracle(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Result<Response, String> {
+    let options = &command.data.options;
+    match (
+        string_option(options, "tables"),
+        int_option(options, "count"),
+    ) {
+        (Some(_), Some(_)) => Err("Give either tables or a count, not both".to_string()),
+        (Some(tables), None) if tables.trim().eq_ignore_ascii_case("list") => {
+            Ok(Response::paginated(crate::oracle_list(ctx, command).await))
+        }
+        (Some(tables), None) => {
+            let words = tables.split_whitespace().collect::<Vec<_>>();
+            crate::oracle_table_response(ctx, command, &words).await
+        }
+        (None, count) => {
+            let count = count.unwrap_or(1);
+            let num_rolls = InputType::try_from(count)
+                .map_err(|_| format!("Invalid number of rolls: {}", count))?;
+            crate::bare_oracle_response(ctx, num_rolls).await
+        }
+    }
+}
+
+/// Attach the channel's history as a file.
+async fn download(ctx: &Context, command: &ApplicationCommandInteraction) -> serenity::Result<()> {
+    #[cfg(not(feature = "download"))]
+    {
+        let response = Response::Messages(vec!["This command is disabled.".to_string()]);
+        respond(ctx, command, response, true).await
+    }
+    #[cfg(feature = "download")]
+    {
+        // Reading a long history can outlast the time allowed to respond.
+        command.defer(&ctx.http).await?;
+        let (history, filename) = crate::channel_history(ctx, command.channel_id).await?;
+        command
+            .create_followup_message(&ctx.http, |m| {
+                m.add_file((history.as_bytes(), filename.as_str()))
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// Parse the options to `/action`.
+fn action_args(options: &[CommandDataOption]) -> Result<ActionArgs, String> {
+    let bonuses = match int_option(options, "bonus") {
+        Some(bonus) => {
+            vec![InputType::try_from(bonus).map_err(|_| format!("Invalid bonus: {}", bonus))?]
+        }
+        None => Vec::new(),
+    };
+    let stat = match string_option(options, "stat") {
+        Some(name) => Some(
+            name.parse::<Stat>()
+                .map_err(|()| format!("Unknown stat: {}", name))?,
+        ),
+        None => None,
+    };
+    let momentum = match int_option(options, "momentum") {
+        Some(momentum) => Some(
+            Momentum::try_from(momentum)
+                .ok()
+                .filter(|m| (MIN_MOMENTUM..=MAX_MOMENTUM).contains(m))
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid momentum: {} (expected {} to {})",
+                        momentum, MIN_MOMENTUM, MAX_MOMENTUM
+                    )
+                })?,
+        ),
+        None => None,
+    };
+    Ok(ActionArgs {
+        bonuses,
+        stat,
+        momentum,
+        cursed: bool_option(options, "cursed"),
+    })
+}
+
+/// Parse the options to `/progress`, returning what to roll for and whether it's cursed.
+fn progress_args(options: &[CommandDataOption]) -> Result<(Progress, bool), String> {
+    let progress = match (
+        int_option(options, "score"),
+        string_option(options, "track"),
+    ) {
+        (Some(_), Some(_)) => return Err("Give either a score or a track, not both".to_string()),
+        (Some(score), None) => Progress::Score(
+            InputType::try_from(score).map_err(|_| format!("Invalid progress score: {}", score))?,
+        ),
+        (None, Some(track)) => Progress::Track(track.trim().to_string()),
+        (None, None) => Progress::Unknown,
+    };
+    Ok((progress, bool_option(options, "cursed")))
+}
+
+/// The value of an option, if it was given.
+fn option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a Value> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref())
+}
+
+fn string_option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    option(options, name)?.as_str()
+}
+
+fn int_option(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    option(options, name)?.as_i64()
+}
+
+/// Flags are off unless given.
+fn bool_option(options: &[CommandDataOption], name: &str) -> bool {
+    option(options, name)
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Respond to a slash command, following up with any further messages.
+/// Ephemeral responses are only shown to the user who gave the command.
+async fn respond(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    response: Response,
+    ephemeral: bool,
+) -> serenity::Result<()> {
+    match response {
+        Response::Messages(messages) => {
+            let mut messages = messages.into_iter();
+            let first = messages.next().unwrap_or_default();
+            command
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|m| m.content(first).ephemeral(ephemeral))
+                })
+                .await?;
+            for message in messages {
+                command
+                    .create_followup_message(&ctx.http, |m| m.content(message).ephemeral(ephemeral))
+                    .await?;
+            }
+        }
+        Response::Attachment {
+            message,
+            filename,
+            contents,
+        } => {
+            command
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|m| {
+                            m.content(message)
+                                .add_file((contents.as_bytes(), filename.as_str()))
+                                .ephemeral(ephemeral)
+                        })
+                })
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(json: Value) -> Vec<CommandDataOption> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn definitions() {
+        let mut commands = CreateApplicationCommands::default();
+        define(&mut commands);
+        let names = commands
+            .0
+            .iter()
+            .map(|command| command["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["action", "progress", "oracle", "roll", "download", "help"]
+        );
+        // Discord rejects the whole set if any name or description is invalid.
+        for command in &commands.0 {
+            let options = command["options"].as_array().cloned().unwrap_or_default();
+            for item in options.iter().chain([command]) {
+                let name = item["name"].as_str().unwrap();
+                assert!(name.len() <= 32 && name == name.to_lowercase(), "{}", name);
+                let description = item["description"].as_str().unwrap();
+                assert!((1..=100).contains(&description.len()), "{}", description);
+            }
+        }
+    }
+
+    #[test]
+    fn help() {
+        // The prefix commands may not work where the slash commands are used.
+        assert!(HELP_TEXT
+            .match_indices("`!")
+            .all(|(i, _)| !HELP_TEXT[i + 2..].starts_with(|c: char| c.is_ascii_lowercase())));
+        for name in ["action", "progress", "oracle", "roll", "download", "help"] {
+            assert!(HELP_TEXT.contains(&format!("(`/{}`)", name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn action() {
+        let args = action_args(&options(serde_json::json!([
+            {"name": "bonus", "type": 4, "value": 2},
+            {"name": "stat", "type": 3, "value": "Wits"},
+            {"name": "momentum", "type": 4, "value": -2},
+            {"name": "cursed", "type": 5, "value": true},
+        ])))
+        .unwrap();
+        assert_eq!(args.bonuses, [2]);
+        assert_eq!(args.stat, Some(Stat::Wits));
+        assert_eq!(args.momentum, Some(-2));
+        assert!(args.cursed);
+
+        let args = action_args(&[]).unwrap();
+        assert!(args.bonuses.is_empty());
+        assert_eq!((args.stat, args.momentum, args.cursed), (None, None, false));
+
+        let momentum = options(serde_json::json!([{"name": "momentum", "type": 4, "value": 11}]));
+        assert_eq!(
+            action_args(&momentum).unwrap_err(),
+            "Invalid momentum: 11 (expected -6 to 10)"
+        );
+    }
+
+    #[test]
+    fn progress() {
+        let score = options(serde_json::json!([{"name": "score", "type": 4, "value": 7}]));
+        assert_eq!(progress_args(&score), Ok((Progress::Score(7), false)));
+        let track = options(serde_json::json!([
+            {"name": "track", "type": 3, "value": " Find the lost ship "},
+            {"name": "cursed", "type": 5, "value": true},
+        ]));
+        assert_eq!(
+            progress_args(&track),
+            Ok((Progress::Track("Find the lost ship".to_string()), true))
+        );
+        assert_eq!(progress_args(&[]), Ok((Progress::Unknown, false)));
+        let both = options(serde_json::json!([
+            {"name": "score", "type": 4, "value": 7},
+            {"name": "track", "type": 3, "value": "Find the lost ship"},
+        ]));
+        assert!(progress_args(&both).is_err());
+    }
+}
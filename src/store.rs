@@ -0,0 +1,97 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+
+/// A collection of records by key, which is saved to a JSON file whenever it changes.
+/// Without a file, the records only last as long as the bot is running.
+pub struct Store<T> {
+    path: Option<PathBuf>,
+    records: Mutex<BTreeMap<String, T>>,
+}
+
+impl<T: Serialize + DeserializeOwned + Clone + Default + Send + 'static> Store<T> {
+    /// Open a store saved at the given path, loading any existing records.
+    pub fn open(path: Option<PathBuf>) -> io::Result<Self> {
+        let records = match &path {
+            Some(path) if path.exists() => serde_json::from_slice(&fs::read(path)?)?,
+            _ => BTreeMap::new(),
+        };
+        Ok(Self {
+            path,
+            records: Mutex::new(records),
+        })
+    }
+
+    /// Get a copy of the record with the given key, or the default if there isn't one.
+    pub async fn get(&self, key: &str) -> T {
+        let records = self.records.lock().await;
+        records.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Change the record with the given key, starting from the default if there isn't one.
+    /// The change is only kept once the store has been saved, so a failed save changes nothing.
+    pub async fn update<R>(&self, key: &str, f: impl FnOnce(&mut T) -> R) -> io::Result<R> {
+        // Holding the lock while saving keeps the saves in the same order as the changes.
+        let mut records = self.records.lock().await;
+        let mut updated = records.clone();
+        let result = f(updated.entry(key.to_string()).or_default());
+        if let Some(path) = &self.path {
+            let contents = serde_json::to_vec_pretty(&updated)?;
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || {
+                // Write to a temporary file first, so a crash can't leave it half-written.
+                let temp = path.with_extension("json.tmp");
+                fs::write(&temp, contents)?;
+                fs::rename(temp, path)
+            })
+            .await
+            .map_err(io::Error::other)??;
+        }
+        *records = updated;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_memory() {
+        let store = Store::<Vec<u32>>::open(None).unwrap();
+        assert_eq!(store.get("a").await, Vec::<u32>::new());
+        store.update("a", |v| v.push(3)).await.unwrap();
+        store.update("a", |v| v.push(4)).await.unwrap();
+        assert_eq!(store.get("a").await, vec![3, 4]);
+        assert_eq!(store.get("b").await, Vec::<u32>::new());
+    }
+
+    #[tokio::test]
+    async fn saved_to_file() {
+        let path =
+            std::env::temp_dir().join(format!("starforged-store-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let store = Store::<Vec<u32>>::open(Some(path.clone())).unwrap();
+        store.update("a", |v| v.push(3)).await.unwrap();
+        drop(store);
+
+        let store = Store::<Vec<u32>>::open(Some(path.clone())).unwrap();
+        assert_eq!(store.get("a").await, vec![3]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_save() {
+        // The directory doesn't exist, so saving fails.
+        let path = std::env::temp_dir()
+            .join(format!("starforged-missing-{}", std::process::id()))
+            .join("store.json");
+        let store = Store::<Vec<u32>>::open(Some(path)).unwrap();
+        assert!(store.update("a", |v| v.push(3)).await.is_err());
+        assert_eq!(store.get("a").await, Vec::<u32>::new());
+    }
+}
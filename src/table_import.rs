@@ -0,0 +1,311 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use crate::oracles::{OracleTable, RollRange, Row, TableError};
+use crate::OutputType;
+
+/// The kinds of file that an oracle table can be imported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Rows like `1-5,Some result`, optionally with a header.
+    Csv,
+    /// A single table in the same shape as the bundled oracles.
+    Json,
+    /// A table like `| 1-5 | Some result |`, optionally with a header.
+    Markdown,
+}
+
+impl Format {
+    /// Work out the format of a file from its extension.
+    pub fn of(filename: &str) -> Option<Self> {
+        let extension = Path::new(filename).extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// A problem with an imported table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// The JSON couldn't be read.
+    Json(String),
+    /// A line couldn't be read as a row, given by its number from 1.
+    BadLine(usize),
+    /// The rows are wrong, for example leaving a gap.
+    Invalid(TableError),
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "Invalid JSON: {}", e),
+            Self::BadLine(line) => write!(
+                f,
+                "Line {} should be a roll or range of rolls followed by a result",
+                line
+            ),
+            Self::Invalid(e) => write!(f, "Invalid table: {}", e),
+        }
+    }
+}
+
+/// Read an oracle table from a file's contents.
+/// If the die is given, the rows must cover exactly that many sides.
+pub fn import(
+    name: String,
+    format: Format,
+    contents: &str,
+    sides: Option<OutputType>,
+) -> Result<OracleTable, ImportError> {
+    let table = match format {
+        Format::Json => {
+            let table: OracleTable =
+                serde_json::from_str(contents).map_err(|e| ImportError::Json(e.to_string()))?;
+            // Aliases could clash with the reserved names, so only the given name is kept.
+            OracleTable {
+                name,
+                aliases: Vec::new(),
+                ..table
+            }
+        }
+        Format::Csv => OracleTable {
+            name,
+            aliases: Vec::new(),
+            rows: rows(contents.lines().map(|line| {
+                let line = line.trim();
+                (!line.is_empty()).then(|| csv_fields(line))
+            }))?,
+        },
+        Format::Markdown => OracleTable {
+            name,
+            aliases: Vec::new(),
+            rows: rows(contents.lines().map(markdown_cells))?,
+        },
+    };
+
+    table.validate().map_err(ImportError::Invalid)?;
+    if let Some(sides) = sides.filter(|&sides| sides != table.sides()) {
+        return Err(ImportError::Invalid(TableError::Uncovered {
+            sides,
+            covered: table.sides(),
+        }));
+    }
+    Ok(table)
+}
+
+/// Read the rows from the fields of each line, where lines that aren't part of the table are
+/// `None`. The first line of the table may be a header.
+fn rows(lines: impl Iterator<Item = Option<Vec<String>>>) -> Result<Vec<Row>, ImportError> {
+    let mut rows = Vec::new();
+    let mut header_allowed = true;
+    for (i, fields) in lines.enumerate() {
+        let Some(fields) = fields else {
+            continue;
+        };
+        match row(&fields) {
+            Some(row) => rows.push(row),
+            None if header_allowed => {}
+            None => return Err(ImportError::BadLine(i + 1)),
+        }
+        header_allowed = false;
+    }
+    Ok(rows)
+}
+
+/// Read a row from its fields: either the ends of the range and a result, or a range like `5`
+/// or `1-5` followed by a result. Any further fields are part of the result.
+fn row(fields: &[String]) -> Option<Row> {
+    let range = |min: &str, max: &str| {
+        Some(RollRange {
+            min: min.trim().parse().ok()?,
+            max: max.trim().parse().ok()?,
+        })
+    };
+    let (roll, text) = match fields {
+        [min, max, text] if range(min, max).is_some() => (range(min, max)?, text.clone()),
+        [roll, text @ ..] if !text.is_empty() => {
+            let (min, max) = roll.split_once(['-', '–']).unwrap_or((roll, roll));
+            (range(min, max)?, text.join(", "))
+        }
+        _ => return None,
+    };
+    Some(Row {
+        roll,
+        text,
+        oracle_rolls: Vec::new(),
+    })
+}
+
+/// Split a line of CSV into its fields, allowing them to be quoted.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            // A doubled quote inside a quoted field is a literal quote.
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// Split a line of a Markdown table into its cells, skipping lines that aren't table rows or
+/// that separate the header.
+fn markdown_cells(line: &str) -> Option<Vec<String>> {
+    let line = line.trim().strip_prefix('|')?;
+    let line = line.strip_suffix('|').unwrap_or(line);
+    let cells = line
+        .split('|')
+        .map(|cell| cell.trim().to_string())
+        .collect::<Vec<_>>();
+    let is_separator = cells
+        .iter()
+        .all(|cell| !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':')));
+    (!is_separator).then_some(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::oracles::{Oracles, TableRoll, MAX_FURTHER_ROLLS};
+
+    fn texts(table: &OracleTable) -> Vec<(OutputType, OutputType, &str)> {
+        table
+            .rows
+            .iter()
+            .map(|row| (row.roll.min, row.roll.max, row.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(Format::of("Table.CSV"), Some(Format::Csv));
+        assert_eq!(Format::of("table.json"), Some(Format::Json));
+        assert_eq!(Format::of("my.table.md"), Some(Format::Markdown));
+        assert_eq!(Format::of("table.txt"), None);
+        assert_eq!(Format::of("table"), None);
+    }
+
+    #[test]
+    fn csv() {
+        let contents =
+            "Roll,Result\n1-3,Calm\n\n4,\"Storm, with \"\"lightning\"\"\"\n5,6,\"Clear, bright\"\n7-8,Windy, wet\n";
+        let table = import("Weather".to_string(), Format::Csv, contents, Some(8)).unwrap();
+        assert_eq!(table.name, "Weather");
+        assert_eq!(
+            texts(&table),
+            [
+                (1, 3, "Calm"),
+                (4, 4, "Storm, with \"lightning\""),
+                (5, 6, "Clear, bright"),
+                (7, 8, "Windy, wet")
+            ]
+        );
+        assert_eq!(
+            import("Bad".to_string(), Format::Csv, "1-3,a\noops\n4-6,b", None),
+            Err(ImportError::BadLine(2))
+        );
+    }
+
+    #[test]
+    fn markdown() {
+        let contents = "# Weather\n\n| Roll | Result |\n|:----:|--------|\n| 1–50 | Calm |\n| 51-100 | Storm |\n";
+        let table = import("Weather".to_string(), Format::Markdown, contents, None).unwrap();
+        assert_eq!(texts(&table), [(1, 50, "Calm"), (51, 100, "Storm")]);
+    }
+
+    #[test]
+    fn json() {
+        let contents = r#"{"name": "Ignored", "rows": [
+            {"roll": {"min": 1, "max": 2}, "text": "Heads"},
+            {"roll": {"min": 3, "max": 4}, "text": "Tails"}
+        ]}"#;
+        let table = import("Coin".to_string(), Format::Json, contents, None).unwrap();
+        assert_eq!(table.name, "Coin");
+        assert_eq!(texts(&table), [(1, 2, "Heads"), (3, 4, "Tails")]);
+        assert!(matches!(
+            import("Coin".to_string(), Format::Json, "{", None),
+            Err(ImportError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn json_references() {
+        // A table that asks to be rolled on again and again is refused.
+        let contents = r#"{"name": "Loop", "aliases": ["list", "1"], "rows": [
+            {"roll": {"min": 1, "max": 1}, "text": "Again",
+             "oracle_rolls": [{"number_of_rolls": 255}]}
+        ]}"#;
+        assert_eq!(
+            import("Loop".to_string(), Format::Json, contents, None),
+            Err(ImportError::Invalid(TableError::TooManyRolls(255)))
+        );
+
+        // Within the limit, it imports without its aliases, and rolling on it stops.
+        let contents = contents.replace("255", "3");
+        let table = import("Loop".to_string(), Format::Json, &contents, None).unwrap();
+        assert!(table.aliases.is_empty());
+        let oracles = Oracles::load(&["[]"]).unwrap().with_custom(vec![table]);
+        let mut rng = StdRng::seed_from_u64(0);
+        let roll = TableRoll::random(&mut rng, &oracles, oracles.find("loop").unwrap());
+        fn count(roll: &TableRoll) -> usize {
+            1 + roll.children.iter().map(count).sum::<usize>()
+        }
+        assert_eq!(count(&roll), 1 + MAX_FURTHER_ROLLS);
+    }
+
+    #[test]
+    fn validation() {
+        let import = |contents, sides| import("Test".to_string(), Format::Csv, contents, sides);
+        assert_eq!(
+            import("1-50,a\n52-100,b", None),
+            Err(ImportError::Invalid(TableError::Gap {
+                expected: 51,
+                found: 52
+            }))
+        );
+        assert_eq!(
+            import("1-50,a\n40-100,b", None),
+            Err(ImportError::Invalid(TableError::Overlap {
+                expected: 51,
+                found: 40
+            }))
+        );
+        assert_eq!(
+            import("1-50,a\n51-90,b", Some(100)),
+            Err(ImportError::Invalid(TableError::Uncovered {
+                sides: 100,
+                covered: 90
+            }))
+        );
+        assert_eq!(
+            import("Roll,Result", None),
+            Err(ImportError::Invalid(TableError::Empty))
+        );
+        // The end of a row can't wrap around to let the next one start at 0.
+        assert_eq!(
+            import("1-4294967295,a\n0-1,b", None),
+            Err(ImportError::Invalid(TableError::TooLarge(RollRange {
+                min: 1,
+                max: OutputType::MAX
+            })))
+        );
+    }
+}
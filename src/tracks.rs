@@ -0,0 +1,247 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::InputType;
+
+/// The number of boxes on a progress track.
+pub const BOXES: u8 = 10;
+/// The number of ticks that fill a single box.
+pub const TICKS_PER_BOX: u8 = 4;
+
+/// What a progress track is tracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrackKind {
+    Vow,
+    Expedition,
+    Combat,
+    Connection,
+}
+
+impl FromStr for TrackKind {
+    type Err = ();
+
+    /// Parse a kind of track from its name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s.to_ascii_lowercase().as_str() {
+            "vow" => Self::Vow,
+            "expedition" => Self::Expedition,
+            "combat" | "fight" => Self::Combat,
+            "connection" => Self::Connection,
+            _ => return Err(()),
+        };
+        Ok(kind)
+    }
+}
+
+impl Display for TrackKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Vow => "Vow",
+            Self::Expedition => "Expedition",
+            Self::Combat => "Combat",
+            Self::Connection => "Connection",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How challenging a progress track is, which determines how quickly it fills.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Rank {
+    Troublesome,
+    Dangerous,
+    Formidable,
+    Extreme,
+    Epic,
+}
+
+impl Rank {
+    /// The number of ticks gained each time progress is marked.
+    pub fn ticks_per_mark(&self) -> u8 {
+        match self {
+            Self::Troublesome => 3 * TICKS_PER_BOX,
+            Self::Dangerous => 2 * TICKS_PER_BOX,
+            Self::Formidable => TICKS_PER_BOX,
+            Self::Extreme => 2,
+            Self::Epic => 1,
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ();
+
+    /// Parse a rank from its name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rank = match s.to_ascii_lowercase().as_str() {
+            "troublesome" => Self::Troublesome,
+            "dangerous" => Self::Dangerous,
+            "formidable" => Self::Formidable,
+            "extreme" => Self::Extreme,
+            "epic" => Self::Epic,
+            _ => return Err(()),
+        };
+        Ok(rank)
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Troublesome => "Troublesome",
+            Self::Dangerous => "Dangerous",
+            Self::Formidable => "Formidable",
+            Self::Extreme => "Extreme",
+            Self::Epic => "Epic",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Render a number of ticks as a row of boxes, each showing how full it is.
+pub fn render_boxes(ticks: u8) -> String {
+    const GLYPHS: [char; 5] = ['○', '◔', '◑', '◕', '●'];
+    (0..BOXES)
+        .map(|i| {
+            let filled = ticks.saturating_sub(i * TICKS_PER_BOX).min(TICKS_PER_BOX);
+            GLYPHS[usize::from(filled)]
+        })
+        .collect()
+}
+
+/// A progress track for a vow, expedition, combat, or connection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressTrack {
+    pub name: String,
+    pub kind: TrackKind,
+    pub rank: Rank,
+    /// The total number of ticks marked, up to a full track.
+    pub ticks: u8,
+    /// Whether this track has been successfully completed with a progress roll.
+    #[serde(default)]
+    pub completed: bool,
+}
+
+impl ProgressTrack {
+    /// A new, empty track.
+    pub fn new(name: String, kind: TrackKind, rank: Rank) -> Self {
+        Self {
+            name,
+            kind,
+            rank,
+            ticks: 0,
+            completed: false,
+        }
+    }
+
+    /// Mark progress on this track the given number of times, according to its rank.
+    pub fn mark(&mut self, times: u8) {
+        let ticks = self.rank.ticks_per_mark().saturating_mul(times);
+        self.ticks = self.ticks.saturating_add(ticks).min(BOXES * TICKS_PER_BOX);
+    }
+
+    /// The progress score of this track, which is the number of completely filled boxes.
+    pub fn score(&self) -> InputType {
+        (self.ticks / TICKS_PER_BOX).into()
+    }
+}
+
+impl Display for ProgressTrack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "**{}** ({} {}): {} {}/{}",
+            self.name,
+            self.rank,
+            self.kind,
+            render_boxes(self.ticks),
+            self.score(),
+            BOXES
+        )?;
+        if self.completed {
+            write!(f, " (Completed)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the index of a track by name, ignoring case.
+pub fn find(tracks: &[ProgressTrack], name: &str) -> Option<usize> {
+    tracks
+        .iter()
+        .position(|track| track.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marking() {
+        let cases = [
+            // (rank, ticks after one mark, marks to fill the track)
+            (Rank::Troublesome, 12, 4),
+            (Rank::Dangerous, 8, 5),
+            (Rank::Formidable, 4, 10),
+            (Rank::Extreme, 2, 20),
+            (Rank::Epic, 1, 40),
+        ];
+        for (rank, ticks, marks) in cases {
+            let mut track = ProgressTrack::new("Test".to_string(), TrackKind::Vow, rank);
+            track.mark(1);
+            assert_eq!(track.ticks, ticks, "{}", rank);
+            track.mark(marks - 2);
+            assert!(track.score() < 10, "{}", rank);
+            track.mark(1);
+            assert_eq!(track.score(), 10, "{}", rank);
+            // A full track stays full.
+            track.mark(u8::MAX);
+            assert_eq!(track.ticks, 40, "{}", rank);
+        }
+    }
+
+    #[test]
+    fn score_counts_full_boxes() {
+        let mut track = ProgressTrack::new("Test".to_string(), TrackKind::Combat, Rank::Extreme);
+        track.mark(3);
+        assert_eq!(track.ticks, 6);
+        assert_eq!(track.score(), 1);
+    }
+
+    #[test]
+    fn rendering() {
+        assert_eq!(render_boxes(0), "○○○○○○○○○○");
+        assert_eq!(render_boxes(9), "●●◔○○○○○○○");
+        assert_eq!(render_boxes(40), "●●●●●●●●●●");
+        let mut track = ProgressTrack::new(
+            "Find the lost ship".to_string(),
+            TrackKind::Vow,
+            Rank::Dangerous,
+        );
+        track.mark(1);
+        assert_eq!(
+            track.to_string(),
+            "**Find the lost ship** (Dangerous Vow): ●●○○○○○○○○ 2/10"
+        );
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!("Vow".parse(), Ok(TrackKind::Vow));
+        assert_eq!("fight".parse(), Ok(TrackKind::Combat));
+        assert_eq!("EPIC".parse(), Ok(Rank::Epic));
+        assert_eq!("legendary".parse::<Rank>(), Err(()));
+    }
+
+    #[test]
+    fn finding() {
+        let tracks = [
+            ProgressTrack::new("Escape".to_string(), TrackKind::Combat, Rank::Epic),
+            ProgressTrack::new("Find the ship".to_string(), TrackKind::Vow, Rank::Epic),
+        ];
+        assert_eq!(find(&tracks, "find THE ship"), Some(1));
+        assert_eq!(find(&tracks, "find"), None);
+    }
+}